@@ -0,0 +1,197 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Duration, Utc};
+use ehttp::Response;
+use serde_json::Value;
+
+use crate::error::GitHubError;
+
+/// The oldest entries are dropped beyond this.
+const MAX_ENTRIES: usize = 1_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CacheUse {
+    /// GitHub answered `304 Not Modified` and the cached body was used, which costs nothing.
+    Hit,
+    Miss,
+    /// Only GET responses are cached.
+    Uncached,
+}
+
+/// A request as it finished, after any retries.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Activity {
+    pub method: String,
+    pub url: String,
+    pub started: DateTime<Utc>,
+    pub duration: Duration,
+    /// What GitHub answered, `None` when no response came back.
+    pub status: Option<u16>,
+    /// Requests counted against the REST quota, or points against the GraphQL one.
+    pub cost: Option<usize>,
+    pub cache: CacheUse,
+    /// Why the request failed, as GitHub or the transport put it.
+    pub error: Option<String>,
+}
+
+impl Activity {
+    pub(crate) fn new(method: &str, url: &str, started: DateTime<Utc>, cache: CacheUse) -> Self {
+        Activity {
+            method: method.to_string(),
+            url: url.to_string(),
+            started,
+            duration: Utc::now() - started,
+            status: None,
+            cost: None,
+            cache,
+            error: None,
+        }
+    }
+
+    pub(crate) fn failed(self, e: &GitHubError) -> Self {
+        Activity { error: Some(e.to_string()), ..self }
+    }
+
+    /// `status` is what GitHub sent, before a `304` was swapped for the cached `response`.
+    /// GraphQL reports errors and cost in the body of a `200`.
+    pub(crate) fn answered(self, status: u16, response: &Response, graphql: bool) -> Self {
+        let (cost, error) = match graphql && response.ok {
+            true => graphql_outcome(&response.bytes),
+            false => (Some(usize::from(self.cache != CacheUse::Hit)), None),
+        };
+        let error = match response.ok {
+            true => error,
+            false => Some(GitHubError::from_response(response).to_string()),
+        };
+        Activity { status: Some(status), cost, error, ..self }
+    }
+
+    /// An attempt that failed with `result` and is sent again after `delay`.
+    pub(crate) fn retried(self, result: &ehttp::Result<Response>, delay: std::time::Duration) -> Self {
+        let (status, e) = match result {
+            Err(e) => (None, GitHubError::Transport(e.clone())),
+            Ok(response) => (Some(response.status), GitHubError::from_response(response)),
+        };
+        Activity { status, error: Some(format!("{e}, retrying in {delay:?}")), ..self }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.error.is_some()
+    }
+}
+
+fn graphql_outcome(body: &[u8]) -> (Option<usize>, Option<String>) {
+    let Ok(body) = serde_json::from_slice::<Value>(body) else {
+        return (None, None);
+    };
+    let cost = body.pointer("/data/rateLimit/cost").and_then(Value::as_u64).map(|cost| cost as usize);
+    let messages = body.get("errors").and_then(Value::as_array).map(|errors| errors.iter()
+        .filter_map(|error| error.get("message").and_then(Value::as_str))
+        .collect::<Vec<_>>()
+        .join(", "));
+    (cost, messages.filter(|it| !it.is_empty()))
+}
+
+/// Every request the client and its clones made, oldest first.
+#[derive(Clone, Default)]
+pub struct ActivityLog(Arc<Mutex<VecDeque<Activity>>>);
+
+impl ActivityLog {
+    pub(crate) fn record(&self, activity: Activity) {
+        let mut entries = self.0.lock().unwrap();
+        entries.push_back(activity);
+        while entries.len() > MAX_ENTRIES {
+            entries.pop_front();
+        }
+    }
+
+    pub fn entries(&self) -> Vec<Activity> {
+        self.0.lock().unwrap().iter().cloned().collect()
+    }
+
+    pub fn clear(&self) {
+        self.0.lock().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod log {
+    use std::sync::Arc;
+
+    use serde_json::json;
+
+    use crate::activity::CacheUse;
+    use crate::github::Client;
+    use crate::retry::RetryPolicy;
+    use crate::transport::{response, FakeTransport};
+
+    fn client(transport: FakeTransport) -> Client {
+        let mut client = Client::default().with_transport(Arc::new(transport));
+        client.set_retry_policy(RetryPolicy::disabled());
+        client
+    }
+
+    #[test]
+    fn cache_hit_costs_nothing() {
+        let client = client(FakeTransport::default()
+            .respond_with("*", Ok(response(200, &[("etag", "abc")], "[]")))
+            .respond_with("*", Ok(response(304, &[], ""))));
+        client.clone().get_path("token", "/orgs/navikt/repos", |_| {});
+        client.clone().get_path("token", "/orgs/navikt/repos", |_| {});
+
+        let entries = client.activity().entries();
+        assert_eq!(entries.iter().map(|it| (it.status, it.cost, it.cache)).collect::<Vec<_>>(), vec![
+            (Some(200), Some(1), CacheUse::Miss),
+            (Some(304), Some(0), CacheUse::Hit),
+        ]);
+        assert_eq!(entries[0].url, "https://api.github.com/orgs/navikt/repos");
+    }
+
+    #[test]
+    fn failure_has_github_message() {
+        let client = client(FakeTransport::default().respond("*", 404, r#"{"message":"Not Found"}"#));
+        client.clone().get_path("token", "/orgs/navikt/teams/unknown", |_| {});
+
+        let entry = client.activity().entries().pop().unwrap();
+        assert!(entry.is_error());
+        assert_eq!(entry.error.unwrap(), "404: Not Found");
+    }
+
+    #[test]
+    fn transport_failure() {
+        let client = client(FakeTransport::default().respond_with("*", Err("connection refused".to_string())));
+        client.clone().get_path("token", "/user", |_| {});
+
+        let entry = client.activity().entries().pop().unwrap();
+        assert_eq!(entry.status, None);
+        assert_eq!(entry.error.unwrap(), "Request failed: connection refused");
+    }
+
+    #[test]
+    fn retried_attempts() {
+        let client = Client::default().with_transport(Arc::new(FakeTransport::default()
+            .respond_with("*", Ok(response(502, &[("retry-after", "2")], r#"{"message":"Server Error"}"#)))
+            .respond_with("*", Ok(response(200, &[], "[]")))));
+        client.clone().get_path("token", "/orgs/navikt/repos", |_| {});
+
+        let entries = client.activity().entries();
+        assert_eq!(entries.iter().map(|it| (it.status, it.error.clone())).collect::<Vec<_>>(), vec![
+            (Some(502), Some("502: Server Error, retrying in 2s".to_string())),
+            (Some(200), None),
+        ]);
+    }
+
+    #[test]
+    fn graphql_cost_and_errors() {
+        let client = client(FakeTransport::default()
+            .respond("https://api.github.com/graphql", 200, r#"{"data":{"rateLimit":{"cost":3}},"errors":[{"message":"Could not resolve to a Repository with the name 'navikt/gone'."}]}"#));
+        client.clone().graphql::<serde_json::Value>("token", &json!({"query": "{}"}), |_| {});
+
+        let entry = client.activity().entries().pop().unwrap();
+        assert_eq!(entry.method, "POST");
+        assert_eq!(entry.cost, Some(3));
+        assert_eq!(entry.cache, CacheUse::Uncached);
+        assert_eq!(entry.error.unwrap(), "Could not resolve to a Repository with the name 'navikt/gone'.");
+    }
+}
@@ -0,0 +1,294 @@
+use std::collections::BTreeMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Duration, Utc};
+use ehttp::{Request, Response};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Keeps the persisted app state well below the ~5 MB browsers allow in localStorage.
+const MAX_BYTES: usize = 2_000_000;
+
+/// A successful response together with the validators GitHub sent for it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Entry {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    status_text: String,
+    headers: BTreeMap<String, String>,
+    body: String,
+    /// Tick of the last lookup, the least recently used entry is evicted first.
+    used: u64,
+    /// When GitHub last sent or confirmed the body, not kept across restarts.
+    #[serde(skip)]
+    received: Option<DateTime<Utc>>,
+}
+
+impl Entry {
+    fn response(&self, url: String) -> Response {
+        Response {
+            url,
+            ok: true,
+            status: 200,
+            status_text: self.status_text.clone(),
+            bytes: self.body.clone().into_bytes(),
+            headers: self.headers.clone(),
+        }
+    }
+}
+
+#[derive(Default, PartialEq, Eq, Serialize, Deserialize)]
+struct Entries {
+    by_key: BTreeMap<String, Entry>,
+    tick: u64,
+}
+
+/// Conditional request cache keyed by credential and url.
+/// A `304 Not Modified` is answered from here and does not count against the rate limit.
+///
+/// Bodies can come from private repositories, so the client does not persist the cache itself.
+#[derive(Clone, Default)]
+pub struct Cache(Arc<Mutex<Entries>>);
+
+/// Responses are only handed back to the credential that fetched them, which is hashed rather than kept.
+fn key(credential: &str, url: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    credential.trim().hash(&mut hasher);
+    format!("{:016x} {url}", hasher.finish())
+}
+
+impl Cache {
+    /// Adds `If-None-Match` / `If-Modified-Since` when a response for the url is cached for `credential`.
+    pub fn conditional(&self, credential: &str, request: &mut Request) {
+        let entries = self.0.lock().unwrap();
+        if let Some(entry) = entries.by_key.get(&key(credential, &request.url)) {
+            if let Some(etag) = &entry.etag {
+                request.headers.insert("If-None-Match".to_string(), etag.clone());
+            }
+            if let Some(last_modified) = &entry.last_modified {
+                request.headers.insert("If-Modified-Since".to_string(), last_modified.clone());
+            }
+        }
+    }
+
+    /// Swaps a `304` for the cached response and stores new validators from a `200`.
+    pub fn resolve(&self, credential: &str, url: &str, response: Response) -> Response {
+        let key = key(credential, url);
+        let mut entries = self.0.lock().unwrap();
+        entries.tick += 1;
+        let tick = entries.tick;
+
+        match response.status {
+            304 => match entries.by_key.get_mut(&key) {
+                Some(entry) => {
+                    entry.used = tick;
+                    entry.received = Some(Utc::now());
+                    let mut cached = entry.response(response.url);
+                    cached.headers.extend(response.headers); // fresh rate limit headers
+                    cached
+                }
+                None => response,
+            },
+            200 => {
+                let etag = response.headers.get("etag").cloned();
+                let last_modified = response.headers.get("last-modified").cloned();
+                match (etag.is_some() || last_modified.is_some(), std::str::from_utf8(&response.bytes)) {
+                    (true, Ok(body)) => {
+                        let entry = Entry {
+                            etag,
+                            last_modified,
+                            status_text: response.status_text.clone(),
+                            headers: response.headers.clone(),
+                            body: body.to_string(),
+                            used: tick,
+                            received: Some(Utc::now()),
+                        };
+                        entries.by_key.insert(key, entry);
+                        entries.evict(MAX_BYTES);
+                    }
+                    _ => { entries.by_key.remove(&key); }
+                }
+                response
+            }
+            _ => response,
+        }
+    }
+
+    /// The cached response for `url` if GitHub sent or confirmed it to `credential` less than `max_age` ago.
+    pub fn fresh(&self, credential: &str, url: &str, max_age: Duration) -> Option<Response> {
+        let mut entries = self.0.lock().unwrap();
+        entries.tick += 1;
+        let tick = entries.tick;
+        let entry = entries.by_key.get_mut(&key(credential, url))?;
+        if !matches!(entry.received, Some(received) if Utc::now() - received < max_age) {
+            return None;
+        }
+        entry.used = tick;
+        Some(entry.response(url.to_string()))
+    }
+
+    pub fn clear(&self) {
+        self.0.lock().unwrap().by_key.clear();
+    }
+
+    /// Takes over the responses of `other`, e.g. once they have been restored from encrypted storage.
+    pub fn replace(&self, other: &Cache) {
+        if !Arc::ptr_eq(&self.0, &other.0) {
+            let entries = std::mem::take(&mut *other.0.lock().unwrap());
+            *self.0.lock().unwrap() = entries;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.lock().unwrap().by_key.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Entries {
+    fn evict(&mut self, max_bytes: usize) {
+        let mut bytes = self.by_key.values().map(|entry| entry.body.len()).sum::<usize>();
+        while bytes > max_bytes {
+            let oldest = self.by_key.iter()
+                .min_by_key(|(_, entry)| entry.used)
+                .map(|(key, _)| key.clone());
+
+            match oldest.and_then(|key| self.by_key.remove(&key)) {
+                Some(entry) => bytes -= entry.body.len(),
+                None => break,
+            }
+        }
+    }
+}
+
+impl Serialize for Cache {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.lock().unwrap().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Cache {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Cache(Arc::new(Mutex::new(Entries::deserialize(deserializer)?))))
+    }
+}
+
+impl PartialEq for Cache {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0) || *self.0.lock().unwrap() == *other.0.lock().unwrap()
+    }
+}
+
+impl Eq for Cache {}
+
+/// Leaves out the bodies, which may be private.
+impl std::fmt::Debug for Cache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Cache").field("entries", &self.len()).finish()
+    }
+}
+
+#[cfg(test)]
+mod conditional {
+    use std::collections::BTreeMap;
+
+    use chrono::Duration;
+    use ehttp::{Request, Response};
+
+    use crate::cache::Cache;
+
+    fn response(status: u16, headers: &[(&str, &str)], body: &str) -> Response {
+        Response {
+            url: "some.url".to_string(),
+            ok: status == 200,
+            status,
+            status_text: "OK".to_string(),
+            bytes: body.as_bytes().to_vec(),
+            headers: headers.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect::<BTreeMap<_, _>>(),
+        }
+    }
+
+    #[test]
+    fn no_headers_without_cached_response() {
+        let mut request = Request::get("some.url");
+        Cache::default().conditional("token", &mut request);
+        assert_eq!(request.headers.get("If-None-Match"), None);
+    }
+
+    #[test]
+    fn validators_are_sent() {
+        let cache = Cache::default();
+        cache.resolve("token", "some.url", response(200, &[("etag", r#"W/"abc""#), ("last-modified", "Thu, 05 Jul 2012 15:31:30 GMT")], "[]"));
+
+        let mut request = Request::get("some.url");
+        cache.conditional("token", &mut request);
+        assert_eq!(request.headers.get("If-None-Match").unwrap(), r#"W/"abc""#);
+        assert_eq!(request.headers.get("If-Modified-Since").unwrap(), "Thu, 05 Jul 2012 15:31:30 GMT");
+    }
+
+    #[test]
+    fn not_modified_returns_cached_body() {
+        let cache = Cache::default();
+        cache.resolve("token", "some.url", response(200, &[("etag", "abc")], "[1,2]"));
+
+        let resolved = cache.resolve("token", "some.url", response(304, &[("x-ratelimit-remaining", "42")], ""));
+        assert_eq!(resolved.status, 200);
+        assert!(resolved.ok);
+        assert_eq!(resolved.bytes, b"[1,2]");
+        assert_eq!(resolved.headers.get("x-ratelimit-remaining").unwrap(), "42");
+    }
+
+    #[test]
+    fn not_cached_without_validators() {
+        let cache = Cache::default();
+        cache.resolve("token", "some.url", response(200, &[], "[]"));
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let cache = Cache::default();
+        cache.resolve("token", "first", response(200, &[("etag", "1")], "1111"));
+        cache.resolve("token", "second", response(200, &[("etag", "2")], "2222"));
+        cache.resolve("token", "first", response(304, &[], ""));
+
+        let mut entries = cache.0.lock().unwrap();
+        entries.evict(4);
+        assert_eq!(entries.by_key.len(), 1);
+        assert!(entries.by_key.keys().all(|key| key.ends_with(" first")));
+    }
+
+    #[test]
+    fn per_credential() {
+        let cache = Cache::default();
+        cache.resolve("token", "some.url", response(200, &[("etag", "abc")], "[1,2]"));
+
+        let mut request = Request::get("some.url");
+        cache.conditional("other token", &mut request);
+        assert_eq!(request.headers.get("If-None-Match"), None);
+        assert!(cache.fresh("other token", "some.url", Duration::minutes(1)).is_none());
+        assert_eq!(cache.fresh("token", "some.url", Duration::minutes(1)).unwrap().bytes, b"[1,2]");
+        assert_eq!(cache.resolve("other token", "some.url", response(304, &[], "")).status, 304);
+    }
+
+    #[test]
+    fn credential_not_stored() {
+        let cache = Cache::default();
+        cache.resolve("ghp_secret", "some.url", response(200, &[("etag", "abc")], "[]"));
+        assert!(!serde_json::to_string(&cache).unwrap().contains("ghp_secret"));
+    }
+
+    #[test]
+    fn persisted() {
+        let cache = Cache::default();
+        cache.resolve("token", "some.url", response(200, &[("etag", "abc")], "[]"));
+
+        let json = serde_json::to_string(&cache).unwrap();
+        let restored = serde_json::from_str::<Cache>(&json).unwrap();
+        assert_eq!(restored.len(), 1);
+    }
+}
@@ -0,0 +1,117 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use ehttp::Response;
+
+use crate::error::GitHubError;
+
+pub(crate) type Callback = Box<dyn FnOnce(Result<Response, GitHubError>) + Send>;
+
+/// GETs on their way, keyed by token and url, with every callback waiting for the response.
+/// A second identical request joins the first instead of being sent.
+#[derive(Clone, Default)]
+pub(crate) struct InFlight(Arc<Mutex<BTreeMap<String, Vec<Callback>>>>);
+
+impl InFlight {
+    pub(crate) fn key(token: &str, url: &str) -> String {
+        format!("{} {url}", token.trim())
+    }
+
+    /// Queues `callback` for the response to `key`, true when it is the first and the request has to be sent.
+    pub(crate) fn join(&self, key: &str, callback: Callback) -> bool {
+        let mut waiting = self.0.lock().unwrap();
+        let callbacks = waiting.entry(key.to_string()).or_default();
+        callbacks.push(callback);
+        callbacks.len() == 1
+    }
+
+    /// Hands the response to everyone who asked for it.
+    pub(crate) fn finish(&self, key: &str, response: Result<Response, GitHubError>) {
+        let callbacks = self.0.lock().unwrap().remove(key).unwrap_or_default();
+        callbacks.into_iter().for_each(|callback| callback(response.clone()));
+    }
+}
+
+#[cfg(test)]
+mod in_flight {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use chrono::Duration as Age;
+    use ehttp::Request;
+
+    use crate::github::Client;
+    use crate::transport::{response, FakeTransport, OnDone, Transport};
+
+    /// Keeps requests open until they are released, so they overlap.
+    #[derive(Default)]
+    struct Held(Mutex<Vec<(Request, OnDone)>>);
+
+    impl Held {
+        fn release(&self, status: u16, body: &str) {
+            let held = std::mem::take(&mut *self.0.lock().unwrap());
+            held.into_iter().for_each(|(_, on_done)| on_done(Ok(response(status, &[("etag", "abc")], body))));
+        }
+
+        fn sent(&self) -> usize {
+            self.0.lock().unwrap().len()
+        }
+    }
+
+    impl Transport for Held {
+        fn fetch(&self, request: Request, on_done: OnDone) {
+            self.0.lock().unwrap().push((request, on_done));
+        }
+
+        fn after(&self, _: Duration, f: Box<dyn FnOnce() + Send>) {
+            f()
+        }
+    }
+
+    fn body_into(bodies: &Arc<Mutex<Vec<String>>>) -> impl FnOnce(Result<ehttp::Response, crate::error::GitHubError>) + Send + 'static {
+        let bodies = bodies.clone();
+        move |response| bodies.lock().unwrap().push(response.unwrap().text().unwrap_or_default().to_string())
+    }
+
+    #[test]
+    fn identical_gets_share_one_request() {
+        let transport = Arc::new(Held::default());
+        let mut client = Client::default().with_transport(transport.clone());
+        let bodies = Arc::new(Mutex::new(vec![]));
+
+        client.get_path("token", "/repos/navikt/api/pulls", body_into(&bodies));
+        client.clone().get_path("token", "/repos/navikt/api/pulls", body_into(&bodies));
+        assert_eq!(transport.sent(), 1);
+
+        transport.release(200, "[1]");
+        assert_eq!(bodies.lock().unwrap().clone(), vec!["[1]", "[1]"]);
+
+        client.get_path("token", "/repos/navikt/api/pulls", body_into(&bodies));
+        assert_eq!(transport.sent(), 1, "sent again once the first has finished");
+    }
+
+    #[test]
+    fn other_token_is_not_shared() {
+        let transport = Arc::new(Held::default());
+        let mut client = Client::default().with_transport(transport.clone());
+        client.get_path("first", "/user", |_| {});
+        client.get_path("second", "/user", |_| {});
+        assert_eq!(transport.sent(), 2);
+    }
+
+    #[test]
+    fn fresh_response_is_reused_within_max_age() {
+        let transport = Arc::new(FakeTransport::default().respond_with("*", Ok(response(200, &[("etag", "abc")], "[1]"))));
+        let mut client = Client::default().with_transport(transport.clone());
+        let bodies = Arc::new(Mutex::new(vec![]));
+
+        client.get_path("token", "/repos/navikt/api/environments", body_into(&bodies));
+        client.with_max_age(Age::seconds(30)).get_path("token", "/repos/navikt/api/environments", body_into(&bodies));
+        assert_eq!(transport.requests().len(), 1);
+        assert_eq!(bodies.lock().unwrap().clone(), vec!["[1]", "[1]"]);
+
+        client.with_max_age(Age::zero()).get_path("token", "/repos/navikt/api/environments", |_| {});
+        client.get_path("token", "/repos/navikt/api/environments", |_| {});
+        assert_eq!(transport.requests().len(), 3);
+    }
+}
@@ -0,0 +1,107 @@
+use std::fmt::Formatter;
+
+use ehttp::Response;
+use serde::Deserialize;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GitHubError {
+    /// The request never got a response, e.g. no network or a CORS failure.
+    Transport(String),
+    /// GitHub answered with a non-success status and the `message` from the body.
+    Status { status: u16, message: String },
+    /// The rate limit is used up until `reset` (epoch seconds).
+    RateLimited { reset: Option<i64> },
+    /// The body did not match the expected type.
+    Deserialize(String),
+    /// A GraphQL query answered with `errors` and no data.
+    GraphQl(String),
+}
+
+/// Body GitHub sends along with error statuses.
+#[derive(Deserialize)]
+struct ErrorBody {
+    message: String,
+}
+
+impl GitHubError {
+    /// Classifies a non-success response.
+    pub fn from_response(response: &Response) -> Self {
+        let remaining = response.headers.get("x-ratelimit-remaining");
+        if matches!(response.status, 403 | 429) && remaining.map(String::as_str) == Some("0") {
+            let reset = response.headers.get("x-ratelimit-reset").and_then(|it| it.parse().ok());
+            return GitHubError::RateLimited { reset };
+        }
+
+        let message = serde_json::from_slice::<ErrorBody>(&response.bytes)
+            .map(|body| body.message)
+            .unwrap_or_else(|_| response.status_text.clone());
+
+        GitHubError::Status { status: response.status, message }
+    }
+
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            GitHubError::Status { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for GitHubError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GitHubError::Transport(e) => write!(f, "Request failed: {e}"),
+            GitHubError::Status { status, message } => write!(f, "{status}: {message}"),
+            GitHubError::RateLimited { reset: Some(reset) } => write!(f, "Rate limit exceeded until {reset}"),
+            GitHubError::RateLimited { reset: None } => write!(f, "Rate limit exceeded"),
+            GitHubError::Deserialize(e) => write!(f, "Unexpected response: {e}"),
+            GitHubError::GraphQl(e) => write!(f, "Query failed: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for GitHubError {}
+
+#[cfg(test)]
+mod from_response {
+    use std::collections::BTreeMap;
+
+    use ehttp::Response;
+
+    use crate::error::GitHubError;
+
+    fn response(status: u16, headers: &[(&str, &str)], body: &str) -> Response {
+        Response {
+            url: "some.url".to_string(),
+            ok: false,
+            status,
+            status_text: "Forbidden".to_string(),
+            bytes: body.as_bytes().to_vec(),
+            headers: headers.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect::<BTreeMap<_, _>>(),
+        }
+    }
+
+    #[test]
+    fn status_with_message() {
+        let response = response(404, &[], r#"{"message":"Not Found","documentation_url":"https://docs.github.com/rest"}"#);
+        assert_eq!(GitHubError::from_response(&response), GitHubError::Status { status: 404, message: "Not Found".to_string() });
+    }
+
+    #[test]
+    fn status_without_body() {
+        let response = response(502, &[], "");
+        assert_eq!(GitHubError::from_response(&response), GitHubError::Status { status: 502, message: "Forbidden".to_string() });
+    }
+
+    #[test]
+    fn rate_limited() {
+        let response = response(403, &[("x-ratelimit-remaining", "0"), ("x-ratelimit-reset", "1372700873")], r#"{"message":"API rate limit exceeded"}"#);
+        assert_eq!(GitHubError::from_response(&response), GitHubError::RateLimited { reset: Some(1372700873) });
+    }
+
+    #[test]
+    fn forbidden_with_quota_left() {
+        let response = response(403, &[("x-ratelimit-remaining", "4999")], r#"{"message":"Resource not accessible by integration"}"#);
+        assert_eq!(GitHubError::from_response(&response).status(), Some(403));
+    }
+}
@@ -0,0 +1,258 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use ehttp::{Request, Response};
+use serde::{Deserialize, Serialize};
+
+use crate::activity::{Activity, ActivityLog, CacheUse};
+use crate::transport::{Ehttp, OnDone, Transport};
+
+/// Directory to record every response to, see [Recorder].
+pub const RECORD_ENV: &str = "AAP_STATUS_RECORD";
+/// Directory to answer every request from, see [Replay].
+pub const REPLAY_ENV: &str = "AAP_STATUS_REPLAY";
+
+/// A response as stored on disk, one file per method and url.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Fixture {
+    method: String,
+    url: String,
+    status: u16,
+    status_text: String,
+    headers: BTreeMap<String, String>,
+    /// UTF-8 bodies are kept readable, anything else is stored as `bytes`.
+    body: Option<String>,
+    bytes: Option<Vec<u8>>,
+}
+
+impl Fixture {
+    fn new(method: &str, response: &Response) -> Self {
+        let body = std::str::from_utf8(&response.bytes).ok().map(str::to_string);
+        Fixture {
+            method: method.to_string(),
+            url: response.url.clone(),
+            status: response.status,
+            status_text: response.status_text.clone(),
+            headers: response.headers.clone(),
+            bytes: body.is_none().then(|| response.bytes.clone()),
+            body,
+        }
+    }
+
+    fn into_response(self) -> Response {
+        Response {
+            url: self.url,
+            ok: (200..300).contains(&self.status),
+            status: self.status,
+            status_text: self.status_text,
+            bytes: self.body.map(String::into_bytes).or(self.bytes).unwrap_or_default(),
+            headers: self.headers,
+        }
+    }
+}
+
+/// File name for a request, readable where possible.
+/// Requests with a body, like GraphQL queries, share their url and are told apart by a hash of it.
+fn file_name(method: &str, url: &str, body: &[u8]) -> String {
+    let slug = url
+        .trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+        .collect::<String>();
+
+    let name = format!("{}_{}", method.to_lowercase(), slug);
+    match (name.len() <= 120, body.is_empty()) {
+        (true, true) => format!("{name}.json"),
+        (true, false) => format!("{name}_{:016x}.json", hash(body)),
+        (false, _) => format!("{}_{:016x}.json", &name[..100], hash(&[url.as_bytes(), body].concat())),
+    }
+}
+
+/// FNV-1a keeps names apart and stable between runs.
+fn hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325_u64, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3))
+}
+
+/// Passes requests on to `inner` and writes every response to `dir`.
+/// Responses that could not be written are recorded in `activity` as failed.
+pub struct Recorder {
+    inner: Arc<dyn Transport>,
+    dir: PathBuf,
+    activity: ActivityLog,
+}
+
+impl Recorder {
+    pub fn new(inner: Arc<dyn Transport>, dir: impl Into<PathBuf>, activity: ActivityLog) -> Self {
+        Recorder { inner, dir: dir.into(), activity }
+    }
+}
+
+impl Transport for Recorder {
+    fn fetch(&self, mut request: Request, on_done: OnDone) {
+        // a 304 has no body worth replaying, so always ask for the full response
+        request.headers.remove("If-None-Match");
+        request.headers.remove("If-Modified-Since");
+
+        let dir = self.dir.clone();
+        let activity = self.activity.clone();
+        let method = request.method.clone();
+        let url = request.url.clone();
+        let name = file_name(&method, &url, &request.body);
+        let started = Utc::now();
+        self.inner.fetch(request, Box::new(move |result| {
+            if let Ok(response) = &result {
+                let fixture = Fixture { url: url.clone(), ..Fixture::new(&method, response) };
+                if let Err(e) = write(&dir, &name, &fixture) {
+                    let entry = Activity::new(&method, &url, started, CacheUse::Uncached);
+                    activity.record(Activity { status: Some(response.status), error: Some(format!("Recording failed: {e}")), ..entry });
+                }
+            }
+            on_done(result)
+        }))
+    }
+
+    fn after(&self, delay: Duration, f: Box<dyn FnOnce() + Send>) {
+        self.inner.after(delay, f)
+    }
+}
+
+fn write(dir: &Path, name: &str, fixture: &Fixture) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let json = serde_json::to_vec_pretty(fixture).map_err(std::io::Error::other)?;
+    std::fs::write(dir.join(name), json)
+}
+
+/// Answers every request from fixtures in `dir`, without token or network.
+/// Requests that were never recorded get a `404`.
+pub struct Replay {
+    dir: PathBuf,
+}
+
+impl Replay {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Replay { dir: dir.into() }
+    }
+}
+
+impl Transport for Replay {
+    fn fetch(&self, request: Request, on_done: OnDone) {
+        let path = self.dir.join(file_name(&request.method, &request.url, &request.body));
+        let fixture = std::fs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<Fixture>(&bytes).ok());
+
+        match fixture {
+            Some(fixture) => on_done(Ok(fixture.into_response())),
+            None => on_done(Ok(Response {
+                url: request.url.clone(),
+                ok: false,
+                status: 404,
+                status_text: "Not Found".to_string(),
+                bytes: format!(r#"{{"message":"No fixture for {} {}"}}"#, request.method, request.url).into_bytes(),
+                headers: BTreeMap::new(),
+            })),
+        }
+    }
+
+    fn after(&self, _: Duration, f: Box<dyn FnOnce() + Send>) {
+        f()
+    }
+}
+
+/// The transport asked for by [RECORD_ENV] or [REPLAY_ENV], if any.
+/// A recorder reports what it failed to write to `activity`.
+pub fn from_env(activity: ActivityLog) -> Option<Arc<dyn Transport>> {
+    if let Some(dir) = std::env::var_os(REPLAY_ENV) {
+        return Some(Arc::new(Replay::new(dir)));
+    }
+
+    std::env::var_os(RECORD_ENV).map(|dir| Arc::new(Recorder::new(Arc::new(Ehttp), dir, activity)) as Arc<dyn Transport>)
+}
+
+#[cfg(test)]
+mod record_and_replay {
+    use std::sync::{Arc, Mutex};
+
+    use ehttp::{Request, Response};
+
+    use crate::activity::ActivityLog;
+    use crate::fixture::{file_name, Recorder, Replay};
+    use crate::transport::{response, FakeTransport, Transport};
+
+    fn fetch(transport: &dyn Transport, request: Request) -> Response {
+        let result = Arc::new(Mutex::new(None));
+        let _result = result.clone();
+        transport.fetch(request, Box::new(move |response| *_result.lock().unwrap() = Some(response)));
+        let response = result.lock().unwrap().take().unwrap().unwrap();
+        response
+    }
+
+    fn dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("aap-status-fixtures-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn replays_what_was_recorded() {
+        let dir = dir("roundtrip");
+        let fake = FakeTransport::default()
+            .respond_with("*", Ok(response(200, &[("etag", "abc"), ("x-ratelimit-remaining", "4999")], r#"[{"id":1}]"#)));
+        let recorder = Recorder::new(Arc::new(fake), &dir, ActivityLog::default());
+        let mut request = Request::get("https://api.github.com/orgs/navikt/teams/aap/repos?per_page=100");
+        request.headers.insert("If-None-Match".to_string(), "abc".to_string());
+        fetch(&recorder, request);
+
+        let replayed = fetch(&Replay::new(&dir), Request::get("https://api.github.com/orgs/navikt/teams/aap/repos?per_page=100"));
+        assert_eq!(replayed.status, 200);
+        assert_eq!(replayed.bytes, br#"[{"id":1}]"#);
+        assert_eq!(replayed.headers.get("etag").unwrap(), "abc");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn binary_bodies() {
+        let dir = dir("binary");
+        let mut zip = response(200, &[], "");
+        zip.bytes = vec![0x50, 0x4b, 0x03, 0x04, 0xff];
+        let recorder = Recorder::new(Arc::new(FakeTransport::default().respond_with("*", Ok(zip))), &dir, ActivityLog::default());
+        fetch(&recorder, Request::get("https://api.github.com/logs"));
+
+        let replayed = fetch(&Replay::new(&dir), Request::get("https://api.github.com/logs"));
+        assert_eq!(replayed.bytes, vec![0x50, 0x4b, 0x03, 0x04, 0xff]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn failed_recording() {
+        let dir = dir("unwritable");
+        std::fs::write(&dir, "a file where the directory should be").unwrap();
+        let activity = ActivityLog::default();
+        let recorder = Recorder::new(Arc::new(FakeTransport::default().respond("*", 200, "{}")), &dir, activity.clone());
+        assert_eq!(fetch(&recorder, Request::get("https://api.github.com/user")).status, 200);
+
+        let entry = activity.entries().pop().unwrap();
+        assert_eq!(entry.url, "https://api.github.com/user");
+        assert!(entry.error.unwrap().starts_with("Recording failed: "));
+        std::fs::remove_file(dir).unwrap();
+    }
+
+    #[test]
+    fn missing_fixture() {
+        let replayed = fetch(&Replay::new(dir("missing")), Request::get("https://api.github.com/user"));
+        assert_eq!(replayed.status, 404);
+    }
+
+    #[test]
+    fn file_names() {
+        assert_eq!(file_name("GET", "https://api.github.com/repos/navikt/aap/pulls?per_page=100", b""), "get_api_github_com_repos_navikt_aap_pulls_per_page_100.json");
+        let long = format!("https://api.github.com/{}", "a".repeat(200));
+        assert_ne!(file_name("GET", &long, b""), file_name("GET", &format!("{long}b"), b""));
+        assert!(file_name("GET", &long, b"").len() < 130);
+        assert_ne!(file_name("POST", "https://api.github.com/graphql", b"{}"), file_name("POST", "https://api.github.com/graphql", b"{ }"));
+    }
+}
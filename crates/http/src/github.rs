@@ -1,91 +1,504 @@
+use std::sync::{Arc, Mutex};
+
+use chrono::{Duration, Utc};
 use ehttp::Request;
 use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::activity::{Activity, ActivityLog, CacheUse};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::app::GitHubApp;
+use crate::cache::Cache;
+use crate::coalesce::InFlight;
+use crate::error::GitHubError;
+use crate::pagination::{self, Limit};
+use crate::rate_limit::{self, RateLimit, RateLimits};
+use crate::retry::{self, RetryPolicy};
+use crate::scheduler::{Priority, Scheduler};
+use crate::settings::Settings;
+use crate::token::TokenInfo;
+use crate::transport::{Ehttp, Transport};
 
 trait GitHubRequest {
     fn github(token: &str, url: &str) -> Request;
+    fn github_post(token: &str, url: &str, body: Vec<u8>) -> Request;
 }
 
-#[derive(Deserialize, Serialize, Clone)] 
+type Listener = Arc<dyn Fn() + Send + Sync>;
+
+/// Clones share their runtime state, so every panel can hold its own copy.
+#[derive(Deserialize, Serialize, Clone)]
 pub struct Client {
-    rate_limit: usize,
-    rate_limit_reset_in_hours: u64,
+    #[serde(skip)]
+    rate_limits: RateLimits,
+    /// Persisted by the caller if at all, see [Client::cache].
+    #[serde(skip)]
+    cache: Cache,
+    #[serde(default)]
+    retry: RetryPolicy,
+    #[serde(default)]
+    settings: Settings,
+    #[serde(skip, default = "default_transport")]
+    transport: Arc<dyn Transport>,
+    #[serde(skip)]
+    scheduler: Scheduler,
+    #[serde(skip)]
+    priority: Priority,
+    #[serde(skip)]
+    activity: ActivityLog,
+    #[serde(skip)]
+    in_flight: InFlight,
+    /// How old a cached response may be and still answer a GET without asking GitHub.
+    #[serde(skip)]
+    max_age: Option<Duration>,
+    #[serde(skip)]
+    on_response: Arc<Mutex<Option<Listener>>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    app: Option<Arc<GitHubApp>>,
+}
+
+fn default_transport() -> Arc<dyn Transport> {
+    Arc::new(Ehttp)
 }
 
 impl Default for Client {
     fn default() -> Self {
         Client {
-            rate_limit: 5000,
-            rate_limit_reset_in_hours: 24,
+            rate_limits: RateLimits::default(),
+            cache: Cache::default(),
+            retry: RetryPolicy::default(),
+            settings: Settings::default(),
+            transport: default_transport(),
+            scheduler: Scheduler::default(),
+            priority: Priority::default(),
+            activity: ActivityLog::default(),
+            in_flight: InFlight::default(),
+            max_age: None,
+            on_response: Arc::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            app: None,
         }
     }
 }
 
 impl Client {
-    pub fn set_rate_limit(&mut self, remaining: usize) {
-        self.rate_limit = remaining;
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    pub fn settings_mut(&mut self) -> &mut Settings {
+        &mut self.settings
+    }
+
+    pub fn set_retry_policy(&mut self, retry: RetryPolicy) {
+        self.retry = retry;
+    }
+
+    /// A clone sharing all other state, whose requests go through `transport`,
+    /// e.g. a [crate::transport::FakeTransport] in tests.
+    pub fn with_transport(&self, transport: Arc<dyn Transport>) -> Client {
+        Client { transport, ..self.clone() }
+    }
+
+    pub(crate) fn transport(&self) -> Arc<dyn Transport> {
+        self.transport.clone()
+    }
+
+    /// A clone sharing all state, whose GETs take a cached response younger than `max_age`
+    /// instead of asking GitHub, e.g. for data another panel has just fetched.
+    pub fn with_max_age(&self, max_age: Duration) -> Client {
+        Client { max_age: Some(max_age), ..self.clone() }
+    }
+
+    /// A clone sharing all state, whose requests wait in the given lane.
+    pub fn with_priority(&self, priority: Priority) -> Client {
+        Client { priority, ..self.clone() }
+    }
+
+    /// Authenticates requests made without a personal token as the App, see [GitHubApp].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_app(&mut self, app: Option<GitHubApp>) {
+        self.app = app.map(Arc::new);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn has_app(&self) -> bool {
+        self.app.is_some()
+    }
+
+    /// Requests sent at the same time by all clones.
+    pub fn set_max_in_flight(&self, max_in_flight: usize) {
+        self.scheduler.set_max_in_flight(max_in_flight);
+    }
+
+    /// Requests waiting for one of the in-flight ones to finish.
+    pub fn queued(&self) -> usize {
+        self.scheduler.queued()
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.scheduler.in_flight()
+    }
+
+    /// Latest quota of the core REST resource, once a response has reported it.
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        self.rate_limits.get(rate_limit::CORE)
     }
 
-    pub fn set_reset_epoch(&mut self, reset_epoch: u64) {
-        let remaining = std::time::UNIX_EPOCH - std::time::Duration::from_secs(reset_epoch);
-        self.rate_limit_reset_in_hours = remaining.elapsed().unwrap().as_secs() / 3600;
+    /// Latest GraphQL quota and the cost of the last query.
+    pub fn graphql_rate_limit(&self) -> Option<RateLimit> {
+        self.rate_limits.get(rate_limit::GRAPHQL)
     }
 
-    pub fn get_rate_limit(&self) -> usize {
-        self.rate_limit
+    /// Calls `listener` after every response has been handed to its callback, e.g. to repaint the UI.
+    pub fn on_response(&self, listener: impl Fn() + Send + Sync + 'static) {
+        *self.on_response.lock().unwrap() = Some(Arc::new(listener));
     }
 
-    pub fn get_rate_reset(&self) -> u64 {
-        self.rate_limit_reset_in_hours
+    /// Every request made by this client and its clones.
+    pub fn activity(&self) -> ActivityLog {
+        self.activity.clone()
+    }
+
+    /// Drops every cached response, e.g. when switching token.
+    pub fn clear_cache(&self) {
+        self.cache.clear();
+    }
+
+    /// The responses cached for all clones, e.g. to store them encrypted.
+    pub fn cache(&self) -> Cache {
+        self.cache.clone()
+    }
+
+    /// Who a GET is answered for, the token or the App installation standing in for a missing one.
+    fn credential(&self, token: &str) -> String {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(app) = self.app.as_ref().filter(|_| token.trim().is_empty()) {
+            return format!("installation {}", app.installation_id());
+        }
+        token.trim().to_string()
     }
 
     pub fn get_path(
         &mut self,
         token: &str,
         path: &str,
-        closure: impl Send + FnOnce(Result<ehttp::Response, String>) + 'static,
+        closure: impl Send + FnOnce(Result<ehttp::Response, GitHubError>) + 'static,
     ) {
-        self.get(token, &format!("https://api.github.com{path}"), closure)
+        self.get(token, &self.settings.api(path), closure)
     }
 
+    /// Fetches `url` as is. Non-success statuses are passed on as responses, see [Client::get_json].
+    /// A GET for the same url and token that is already on its way is joined instead of sent again.
     pub fn get(
         &mut self,
         token: &str,
         url: &str,
-        closure: impl Send + FnOnce(Result<ehttp::Response, String>) + 'static,
+        closure: impl Send + FnOnce(Result<ehttp::Response, GitHubError>) + 'static,
+    ) {
+        let credential = self.credential(token);
+        if let Some(response) = self.max_age.and_then(|max_age| self.cache.fresh(&credential, url, max_age)) {
+            return closure(Ok(response));
+        }
+
+        let key = InFlight::key(token, url);
+        if !self.in_flight.join(&key, Box::new(closure)) {
+            return;
+        }
+
+        let mut request = Request::github(token, url);
+        self.cache.conditional(&credential, &mut request);
+        let in_flight = self.in_flight.clone();
+        self.send(request, Some(credential), move |response| in_flight.finish(&key, response))
+    }
+
+    pub fn post_path(
+        &mut self,
+        token: &str,
+        path: &str,
+        body: &impl Serialize,
+        closure: impl Send + FnOnce(Result<ehttp::Response, GitHubError>) + 'static,
+    ) {
+        self.post(token, &self.settings.api(path), body, closure)
+    }
+
+    /// Sends `body` as JSON to `url`, e.g. to re-run a workflow.
+    /// Unlike [Client::get] non-success statuses are errors, and nothing is retried.
+    pub fn post(
+        &mut self,
+        token: &str,
+        url: &str,
+        body: &impl Serialize,
+        closure: impl Send + FnOnce(Result<ehttp::Response, GitHubError>) + 'static,
+    ) {
+        let body = match serde_json::to_vec(body) {
+            Ok(body) => body,
+            Err(e) => return closure(Err(GitHubError::Deserialize(e.to_string()))),
+        };
+
+        self.send(Request::github_post(token, url, body), None, move |response| {
+            closure(response.and_then(|response| match response.ok {
+                true => Ok(response),
+                false => Err(GitHubError::from_response(&response)),
+            }))
+        })
+    }
+
+    /// Runs a GraphQL `query` and deserializes its `data`.
+    /// Partial data is passed on as long as there is some, the errors are only recorded in the [Client::activity].
+    pub fn graphql<T: DeserializeOwned>(
+        &mut self,
+        token: &str,
+        query: &impl Serialize,
+        closure: impl Send + FnOnce(Result<T, GitHubError>) + 'static,
     ) {
-        println!("Fetching {}", &url);
-
-        ehttp::fetch(Request::github(token, url), |response| {
-            match response {
-                Err(e) => closure(Err(format!("ehttp fetch failed: {}", e))),
-                Ok(response) => {
-                    closure(
-                        Ok(response)
-                        /*
-                        serde_json::from_slice::<T>(&response.bytes).map_err(|e| format!(
-                            "Deserializing from slice failed:{}. Status:{}, Status Text:{}",
-                            e,
-                            &response.status,
-                            &response.status_text,
-                        ))
-                        */
-                    )
+        let url = self.settings.graphql_url();
+
+        let body = match serde_json::to_vec(query) {
+            Ok(body) => body,
+            Err(e) => return closure(Err(GitHubError::Deserialize(e.to_string()))),
+        };
+
+        let rate_limits = self.rate_limits.clone();
+        self.send(Request::github_post(token, &url, body), None, move |response| {
+            let data = response.and_then(|response| json::<GraphQlResponse>(&response)).and_then(|response| {
+                let messages = response.errors.into_iter().map(|error| error.message).collect::<Vec<_>>();
+                match response.data {
+                    Some(data) if !data.is_null() => Ok(data),
+                    _ => Err(GitHubError::GraphQl(messages.join(", "))),
                 }
+            });
+
+            if let Some(cost) = data.as_ref().ok().and_then(|data| data.pointer("/rateLimit/cost")).and_then(Value::as_u64) {
+                rate_limits.set_cost(rate_limit::GRAPHQL, cost as usize);
             }
+
+            closure(data.and_then(|data| serde_json::from_value::<T>(data).map_err(|e| GitHubError::Deserialize(e.to_string()))))
         })
     }
+
+    /// Sends `request`, as the App when there is one and no personal token was given.
+    /// The response is cached for `credential`, if given.
+    fn send(
+        &mut self,
+        mut request: Request,
+        credential: Option<String>,
+        closure: impl Send + FnOnce(Result<ehttp::Response, GitHubError>) + 'static,
+    ) {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(app) = self.app.clone().filter(|_| !request.headers.contains_key("Authorization")) {
+            let mut client = self.clone();
+            return app.token(self.transport.clone(), &self.settings.api_url, self.activity.clone(), Box::new(move |token| match token {
+                Ok(token) => {
+                    request.headers.insert("Authorization".to_string(), format!("Bearer {token}"));
+                    client.schedule(request, credential, closure)
+                }
+                Err(e) => closure(Err(e)),
+            }));
+        }
+
+        self.schedule(request, credential, closure)
+    }
+
+    /// Sends `request` through the scheduler, retrying GETs and GraphQL queries.
+    /// Only GET responses are cached, everything else shares its url with unrelated requests.
+    fn schedule(
+        &mut self,
+        request: Request,
+        credential: Option<String>,
+        closure: impl Send + FnOnce(Result<ehttp::Response, GitHubError>) + 'static,
+    ) {
+        let rate_limits = self.rate_limits.clone();
+        let cache = credential.filter(|_| request.method == "GET").map(|credential| (self.cache.clone(), credential));
+        let url = request.url.clone();
+        let method = request.method.clone();
+        let graphql = url == self.settings.graphql_url();
+        let activity = self.activity.clone();
+        let on_response = self.on_response.clone();
+        let transport = self.transport.clone();
+        // queries have no side effects, unlike other POSTs
+        let retry = match method == "GET" || graphql {
+            true => self.retry.clone(),
+            false => RetryPolicy::disabled(),
+        };
+        self.scheduler.submit(self.priority, move |slot| {
+            let started = Utc::now();
+            retry::fetch(transport, retry, activity.clone(), request, 1, Box::new(move |response| {
+                drop(slot);
+                match response {
+                    Err(e) => {
+                        let e = GitHubError::Transport(e);
+                        let cache_use = match cache {
+                            Some(_) => CacheUse::Miss,
+                            None => CacheUse::Uncached,
+                        };
+                        activity.record(Activity::new(&method, &url, started, cache_use).failed(&e));
+                        closure(Err(e))
+                    }
+                    Ok(response) => {
+                        rate_limits.update(&response.headers);
+                        let status = response.status;
+                        let (response, cache_use) = match cache {
+                            Some((cache, credential)) => {
+                                let response = cache.resolve(&credential, &url, response);
+                                let cache_use = match status == 304 && response.status == 200 {
+                                    true => CacheUse::Hit,
+                                    false => CacheUse::Miss,
+                                };
+                                (response, cache_use)
+                            }
+                            None => (response, CacheUse::Uncached),
+                        };
+                        activity.record(Activity::new(&method, &url, started, cache_use).answered(status, &response, graphql));
+                        closure(Ok(response))
+                    }
+                }
+
+                let listener = on_response.lock().unwrap().clone();
+                if let Some(listener) = listener {
+                    listener()
+                }
+            }))
+        })
+    }
+
+    /// Who `token` belongs to, its scopes and expiry.
+    pub fn token_info(
+        &mut self,
+        token: &str,
+        closure: impl Send + FnOnce(Result<TokenInfo, GitHubError>) + 'static,
+    ) {
+        self.get_path(token, "/user", move |response| {
+            closure(response.and_then(|response| TokenInfo::from_response(&response)))
+        })
+    }
+
+    pub fn get_json_path<T: DeserializeOwned>(
+        &mut self,
+        token: &str,
+        path: &str,
+        closure: impl Send + FnOnce(Result<T, GitHubError>) + 'static,
+    ) {
+        self.get_json(token, &self.settings.api(path), closure)
+    }
+
+    /// Fetches `url` and deserializes the body of a successful response.
+    pub fn get_json<T: DeserializeOwned>(
+        &mut self,
+        token: &str,
+        url: &str,
+        closure: impl Send + FnOnce(Result<T, GitHubError>) + 'static,
+    ) {
+        self.get(token, url, move |response| {
+            closure(response.and_then(|response| json(&response)))
+        })
+    }
+
+    pub fn get_all_path<T: DeserializeOwned>(
+        &mut self,
+        token: &str,
+        path: &str,
+        limit: Limit,
+        closure: impl Send + FnOnce(Result<Vec<T>, GitHubError>) + 'static,
+    ) {
+        self.get_all(token, &self.settings.api(path), limit, closure)
+    }
+
+    /// Fetches every page of a listing by following the `Link: rel="next"` header,
+    /// stopping early when the [Limit] is reached.
+    pub fn get_all<T: DeserializeOwned>(
+        &mut self,
+        token: &str,
+        url: &str,
+        limit: Limit,
+        closure: impl Send + FnOnce(Result<Vec<T>, GitHubError>) + 'static,
+    ) {
+        self.get_pages(token.to_string(), pagination::with_per_page(url), limit, vec![], 0, move |items| {
+            closure(items.and_then(|items| serde_json::from_value::<Vec<T>>(Value::Array(items))
+                .map_err(|e| GitHubError::Deserialize(e.to_string()))))
+        })
+    }
+
+    fn get_pages(
+        &mut self,
+        token: String,
+        url: String,
+        limit: Limit,
+        mut items: Vec<Value>,
+        pages: usize,
+        closure: impl Send + FnOnce(Result<Vec<Value>, GitHubError>) + 'static,
+    ) {
+        let mut client = self.clone();
+        self.get(&token.clone(), &url, move |response| {
+            let response = match response {
+                Ok(response) if response.ok => response,
+                Ok(response) => return closure(Err(GitHubError::from_response(&response))),
+                Err(e) => return closure(Err(e)),
+            };
+
+            match pagination::page_items(&response.bytes) {
+                Err(e) => closure(Err(GitHubError::Deserialize(e))),
+                Ok(page) => {
+                    items.extend(page);
+                    match pagination::next_link(&response.headers) {
+                        Some(next) if !limit.reached(pages + 1, items.len()) => {
+                            client.get_pages(token, next, limit, items, pages + 1, closure)
+                        }
+                        _ => {
+                            limit.truncate(&mut items);
+                            closure(Ok(items))
+                        }
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Envelope of every GraphQL response.
+#[derive(Deserialize)]
+struct GraphQlResponse {
+    data: Option<Value>,
+    #[serde(default)]
+    errors: Vec<GraphQlError>,
+}
+
+#[derive(Deserialize)]
+struct GraphQlError {
+    message: String,
+}
+
+/// Body of a successful response as `T`, otherwise the classified error.
+fn json<T: DeserializeOwned>(response: &ehttp::Response) -> Result<T, GitHubError> {
+    if !response.ok {
+        return Err(GitHubError::from_response(response));
+    }
+
+    serde_json::from_slice::<T>(&response.bytes).map_err(|e| GitHubError::Deserialize(e.to_string()))
 }
 
 impl GitHubRequest for Request {
+    /// Without a token the request is anonymous, or made as the App if the client has one.
     fn github(token: &str, url: &str) -> Request {
-        Request {
+        let mut request = Request {
             headers: ehttp::headers(&[
                 ("Accept", "application/vnd.github+json"),
                 ("User-Agent", "rust, ehttp::fetch"),
-                ("Authorization", format!("Bearer {}", token.trim()).as_str()),
             ]),
             ..Request::get(url)
+        };
+        if !token.trim().is_empty() {
+            request.headers.insert("Authorization".to_string(), format!("Bearer {}", token.trim()));
         }
+        request
+    }
+
+    fn github_post(token: &str, url: &str, body: Vec<u8>) -> Request {
+        let mut request = Request { method: "POST".to_string(), body, ..Request::github(token, url) };
+        request.headers.insert("Content-Type".to_string(), "application/json".to_string());
+        request
     }
 }
 
@@ -113,6 +526,12 @@ mod request {
         assert_eq!(request.headers.get("Authorization").unwrap(), "Bearer secret.token");
     }
 
+    #[test]
+    fn anonymous() {
+        let request = Request::github(" ", "some.url");
+        assert_eq!(request.headers.get("Authorization"), None);
+    }
+
     #[test]
     fn user_agent_header() {
         let request = Request::github("secret.token", "some.url");
@@ -130,4 +549,261 @@ mod request {
         let request = Request::github("secret.token", "some.url");
         assert_eq!(request.body, Vec::<u8>::new());
     }
+
+    #[test]
+    fn post() {
+        let request = Request::github_post("secret.token", "some.url", b"{}".to_vec());
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.body, b"{}".to_vec());
+        assert_eq!(request.headers.get("Content-Type").unwrap(), "application/json");
+        assert_eq!(request.headers.get("Authorization").unwrap(), "Bearer secret.token");
+    }
+}
+
+#[cfg(test)]
+mod json {
+    use std::collections::BTreeMap;
+
+    use ehttp::Response;
+
+    use crate::error::GitHubError;
+    use crate::github::json;
+
+    fn response(status: u16, body: &str) -> Response {
+        Response {
+            url: "some.url".to_string(),
+            ok: (200..300).contains(&status),
+            status,
+            status_text: String::default(),
+            bytes: body.as_bytes().to_vec(),
+            headers: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn success() {
+        let ids = json::<Vec<i64>>(&response(200, "[1,2]")).unwrap();
+        assert_eq!(ids, vec![1, 2]);
+    }
+
+    #[test]
+    fn unauthorized() {
+        let error = json::<Vec<i64>>(&response(401, r#"{"message":"Bad credentials"}"#)).unwrap_err();
+        assert_eq!(error, GitHubError::Status { status: 401, message: "Bad credentials".to_string() });
+    }
+
+    #[test]
+    fn schema_mismatch() {
+        let error = json::<Vec<i64>>(&response(200, r#"{"id":1}"#)).unwrap_err();
+        assert!(matches!(error, GitHubError::Deserialize(_)));
+    }
+}
+
+#[cfg(test)]
+mod get_all {
+    use std::sync::{Arc, Mutex};
+
+    use crate::github::Client;
+    use crate::pagination::Limit;
+    use crate::transport::{response, FakeTransport};
+
+    fn get_all(transport: FakeTransport, limit: Limit) -> (Arc<FakeTransport>, Vec<i64>) {
+        let transport = Arc::new(transport);
+        let ids = Arc::new(Mutex::new(vec![]));
+        let _ids = ids.clone();
+        Client::default().with_transport(transport.clone()).get_all::<i64>("token", "https://some.url/items", limit, move |result| {
+            *_ids.lock().unwrap() = result.unwrap();
+        });
+        let ids = ids.lock().unwrap().clone();
+        (transport, ids)
+    }
+
+    fn pages() -> FakeTransport {
+        FakeTransport::default()
+            .respond_with("*page=2", Ok(response(200, &[("link", r#"<https://some.url/items?per_page=100&page=3>; rel="next""#)], "[3,4]")))
+            .respond_with("*page=3", Ok(response(200, &[], "[5]")))
+            .respond_with("*", Ok(response(200, &[("link", r#"<https://some.url/items?per_page=100&page=2>; rel="next""#)], "[1,2]")))
+    }
+
+    #[test]
+    fn follows_next_links() {
+        let (transport, ids) = get_all(pages(), Limit::default());
+        assert_eq!(ids, vec![1, 2, 3, 4, 5]);
+        assert_eq!(transport.requests(), vec![
+            "https://some.url/items?per_page=100",
+            "https://some.url/items?per_page=100&page=2",
+            "https://some.url/items?per_page=100&page=3",
+        ]);
+    }
+
+    #[test]
+    fn page_limit() {
+        let (transport, ids) = get_all(pages(), Limit::pages(2));
+        assert_eq!(ids, vec![1, 2, 3, 4]);
+        assert_eq!(transport.requests().len(), 2);
+    }
+
+    #[test]
+    fn item_limit() {
+        let (transport, ids) = get_all(pages(), Limit::items(3));
+        assert_eq!(ids, vec![1, 2, 3]);
+        assert_eq!(transport.requests().len(), 2);
+    }
+}
+
+#[cfg(test)]
+mod graphql {
+    use std::sync::{Arc, Mutex};
+
+    use serde_json::{json, Value};
+
+    use crate::error::GitHubError;
+    use crate::github::Client;
+    use crate::transport::{response, FakeTransport};
+
+    fn query(body: &str) -> (Client, Result<Value, GitHubError>) {
+        let headers = [("x-ratelimit-limit", "5000"), ("x-ratelimit-remaining", "4990"), ("x-ratelimit-resource", "graphql")];
+        let transport = FakeTransport::default().respond_with("https://api.github.com/graphql", Ok(response(200, &headers, body)));
+        let client = Client::default().with_transport(Arc::new(transport));
+        let result = Arc::new(Mutex::new(None));
+        let _result = result.clone();
+        client.clone().graphql::<Value>("token", &json!({"query": "{ viewer { login } }"}), move |data| *_result.lock().unwrap() = Some(data));
+        let result = result.lock().unwrap().take().unwrap();
+        (client, result)
+    }
+
+    #[test]
+    fn data() {
+        let (client, data) = query(r#"{"data":{"rateLimit":{"cost":3},"viewer":{"login":"octocat"}}}"#);
+        assert_eq!(data.unwrap().pointer("/viewer/login").unwrap(), "octocat");
+        let rate_limit = client.graphql_rate_limit().unwrap();
+        assert_eq!(rate_limit.remaining, 4990);
+        assert_eq!(rate_limit.cost, Some(3));
+    }
+
+    #[test]
+    fn partial_data() {
+        let (_, data) = query(r#"{"data":{"r0":null},"errors":[{"message":"Could not resolve to a Repository"}]}"#);
+        assert_eq!(data.unwrap(), json!({"r0": null}));
+    }
+
+    #[test]
+    fn retried() {
+        let transport = Arc::new(FakeTransport::default()
+            .respond("https://api.github.com/graphql", 502, "")
+            .respond("https://api.github.com/graphql", 200, r#"{"data":{"viewer":{"login":"octocat"}}}"#));
+        let result = Arc::new(Mutex::new(None));
+        let _result = result.clone();
+        Client::default().with_transport(transport.clone()).graphql::<Value>("token", &json!({"query": "{ viewer { login } }"}), move |data| *_result.lock().unwrap() = Some(data));
+        assert!(result.lock().unwrap().take().unwrap().is_ok());
+        assert_eq!(transport.requests().len(), 2);
+    }
+
+    #[test]
+    fn errors_without_data() {
+        let (_, data) = query(r#"{"errors":[{"message":"Field 'nope' doesn't exist"}]}"#);
+        assert_eq!(data.unwrap_err(), GitHubError::GraphQl("Field 'nope' doesn't exist".to_string()));
+    }
+}
+
+#[cfg(test)]
+mod post {
+    use std::sync::{Arc, Mutex};
+
+    use serde_json::json;
+
+    use crate::error::GitHubError;
+    use crate::github::Client;
+    use crate::transport::FakeTransport;
+
+    fn post(transport: FakeTransport) -> (Arc<FakeTransport>, Result<u16, GitHubError>) {
+        let transport = Arc::new(transport);
+        let result = Arc::new(Mutex::new(None));
+        let _result = result.clone();
+        Client::default().with_transport(transport.clone()).post("token", "https://some.url/rerun", &json!({}), move |response| {
+            *_result.lock().unwrap() = Some(response.map(|response| response.status));
+        });
+        let result = result.lock().unwrap().take().unwrap();
+        (transport, result)
+    }
+
+    #[test]
+    fn created() {
+        let (_, result) = post(FakeTransport::default().respond("https://some.url/rerun", 201, ""));
+        assert_eq!(result, Ok(201));
+    }
+
+    #[test]
+    fn failure_is_an_error() {
+        let (_, result) = post(FakeTransport::default().respond("*", 403, r#"{"message":"Must have admin rights to Repository."}"#));
+        assert_eq!(result, Err(GitHubError::Status { status: 403, message: "Must have admin rights to Repository.".to_string() }));
+    }
+
+    #[test]
+    fn never_retried() {
+        let (transport, result) = post(FakeTransport::default().respond("*", 502, "").respond("*", 201, ""));
+        assert_eq!(result.unwrap_err().status(), Some(502));
+        assert_eq!(transport.requests().len(), 1);
+    }
+}
+
+#[cfg(test)]
+mod on_response {
+    use std::sync::{Arc, Mutex};
+
+    use crate::github::Client;
+    use crate::transport::FakeTransport;
+
+    #[test]
+    fn called_after_the_callback() {
+        let events = Arc::new(Mutex::new(vec![]));
+        let client = Client::default().with_transport(Arc::new(FakeTransport::default().respond("*", 200, "[]")));
+        let _events = events.clone();
+        client.on_response(move || _events.lock().unwrap().push("repaint"));
+
+        let _events = events.clone();
+        client.clone().get_path("token", "/user", move |_| _events.lock().unwrap().push("callback"));
+        assert_eq!(events.lock().unwrap().clone(), vec!["callback", "repaint"]);
+    }
+}
+
+#[cfg(test)]
+mod app {
+    use std::sync::Arc;
+
+    use chrono::{Duration, Utc};
+
+    use crate::app::GitHubApp;
+    use crate::github::Client;
+    use crate::transport::FakeTransport;
+
+    fn client(transport: Arc<FakeTransport>) -> Client {
+        let mut client = Client::default().with_transport(transport);
+        client.set_app(Some(GitHubApp::new(1, 2, include_bytes!("../testdata/github-app.pem")).unwrap()));
+        client
+    }
+
+    fn transport() -> Arc<FakeTransport> {
+        let expires_at = (Utc::now() + Duration::hours(1)).to_rfc3339();
+        Arc::new(FakeTransport::default()
+            .respond("*/access_tokens", 201, &format!(r#"{{"token":"ghs_installation","expires_at":"{expires_at}"}}"#))
+            .respond("*", 200, "{}"))
+    }
+
+    #[test]
+    fn without_personal_token() {
+        let transport = transport();
+        client(transport.clone()).get_path("", "/orgs/navikt/teams/aap", |_| {});
+        assert_eq!(transport.requests(), vec![
+            "https://api.github.com/app/installations/2/access_tokens",
+            "https://api.github.com/orgs/navikt/teams/aap",
+        ]);
+    }
+
+    #[test]
+    fn personal_token_first() {
+        let transport = transport();
+        client(transport.clone()).get_path("ghp_personal", "/orgs/navikt/teams/aap", |_| {});
+        assert_eq!(transport.requests(), vec!["https://api.github.com/orgs/navikt/teams/aap"]);
+    }
 }
@@ -1 +1,17 @@
+pub mod activity;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod app;
+pub mod cache;
+pub mod coalesce;
+pub mod device_flow;
+pub mod error;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod fixture;
 pub mod github;
+pub mod pagination;
+pub mod rate_limit;
+pub mod retry;
+pub mod scheduler;
+pub mod settings;
+pub mod token;
+pub mod transport;
@@ -0,0 +1,146 @@
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+/// GitHub allows at most 100 items per page.
+pub const MAX_PER_PAGE: usize = 100;
+
+/// Keys GitHub puts next to the item array when a listing is wrapped in an object,
+/// e.g. `{ "total_count": 2, "workflow_runs": [...] }`.
+const NAMESPACE_METADATA: [&str; 3] = ["total_count", "incomplete_results", "repository_selection"];
+
+/// Upper bound on how much of a paginated listing to fetch.
+/// The default follows every `rel="next"` link until the listing is exhausted.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct Limit {
+    pages: Option<usize>,
+    items: Option<usize>,
+}
+
+impl Limit {
+    pub fn pages(pages: usize) -> Self {
+        Limit { pages: Some(pages), items: None }
+    }
+
+    pub fn items(items: usize) -> Self {
+        Limit { pages: None, items: Some(items) }
+    }
+
+    pub(crate) fn reached(&self, pages: usize, items: usize) -> bool {
+        self.pages.is_some_and(|max| pages >= max) || self.items.is_some_and(|max| items >= max)
+    }
+
+    pub(crate) fn truncate(&self, items: &mut Vec<Value>) {
+        if let Some(max) = self.items {
+            items.truncate(max);
+        }
+    }
+}
+
+/// Adds `per_page=100` unless the url already asks for a page size.
+pub(crate) fn with_per_page(url: &str) -> String {
+    if url.contains("per_page=") {
+        return url.to_string();
+    }
+
+    let separator = if url.contains('?') { '&' } else { '?' };
+    format!("{url}{separator}per_page={MAX_PER_PAGE}")
+}
+
+/// Url of the `rel="next"` entry in a `Link` header, if any.
+pub(crate) fn next_link(headers: &BTreeMap<String, String>) -> Option<String> {
+    headers.get("link")?
+        .split(',')
+        .find_map(|link| {
+            let (url, params) = link.split_once(';')?;
+            params.split(';')
+                .any(|param| param.trim() == r#"rel="next""#)
+                .then(|| url.trim().trim_start_matches('<').trim_end_matches('>').to_string())
+        })
+}
+
+/// Items of a single page. Listings wrapped in an object are unwrapped to their item array.
+pub(crate) fn page_items(bytes: &[u8]) -> Result<Vec<Value>, String> {
+    match serde_json::from_slice::<Value>(bytes).map_err(|e| format!("Deserializing page failed: {e}"))? {
+        Value::Array(items) => Ok(items),
+        Value::Object(mut fields) => {
+            let key = fields.iter()
+                .find(|(key, value)| value.is_array() && !NAMESPACE_METADATA.contains(&key.as_str()))
+                .map(|(key, _)| key.clone())
+                .ok_or_else(|| "Page contains no list of items".to_string())?;
+
+            match fields.remove(&key) {
+                Some(Value::Array(items)) => Ok(items),
+                _ => unreachable!("key was found as an array above"),
+            }
+        }
+        _ => Err("Page is neither a list nor an object".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod link {
+    use std::collections::BTreeMap;
+
+    use crate::pagination::next_link;
+
+    fn headers(link: &str) -> BTreeMap<String, String> {
+        BTreeMap::from([("link".to_string(), link.to_string())])
+    }
+
+    #[test]
+    fn next() {
+        let link = r#"<https://api.github.com/repositories/1300192/issues?page=2>; rel="prev", <https://api.github.com/repositories/1300192/issues?page=4>; rel="next", <https://api.github.com/repositories/1300192/issues?page=515>; rel="last", <https://api.github.com/repositories/1300192/issues?page=1>; rel="first""#;
+        assert_eq!(next_link(&headers(link)).unwrap(), "https://api.github.com/repositories/1300192/issues?page=4");
+    }
+
+    #[test]
+    fn last_page() {
+        let link = r#"<https://api.github.com/repositories/1300192/issues?page=514>; rel="prev", <https://api.github.com/repositories/1300192/issues?page=1>; rel="first""#;
+        assert_eq!(next_link(&headers(link)), None);
+    }
+
+    #[test]
+    fn missing() {
+        assert_eq!(next_link(&BTreeMap::new()), None);
+    }
+}
+
+#[cfg(test)]
+mod page {
+    use serde_json::json;
+
+    use crate::pagination::{Limit, page_items, with_per_page};
+
+    #[test]
+    fn array() {
+        let items = page_items(br#"[{"id":1},{"id":2}]"#).unwrap();
+        assert_eq!(items, vec![json!({"id":1}), json!({"id":2})]);
+    }
+
+    #[test]
+    fn namespaced() {
+        let items = page_items(br#"{"total_count":2,"workflow_runs":[{"id":1},{"id":2}]}"#).unwrap();
+        assert_eq!(items, vec![json!({"id":1}), json!({"id":2})]);
+    }
+
+    #[test]
+    fn not_a_list() {
+        assert!(page_items(br#"{"message":"Not Found"}"#).is_err());
+    }
+
+    #[test]
+    fn per_page() {
+        assert_eq!(with_per_page("https://some.url/repos"), "https://some.url/repos?per_page=100");
+        assert_eq!(with_per_page("https://some.url/pulls?state=open"), "https://some.url/pulls?state=open&per_page=100");
+        assert_eq!(with_per_page("https://some.url/runs?per_page=15"), "https://some.url/runs?per_page=15");
+    }
+
+    #[test]
+    fn limit() {
+        assert!(!Limit::default().reached(1000, 100_000));
+        assert!(Limit::pages(2).reached(2, 0));
+        assert!(!Limit::pages(2).reached(1, 100));
+        assert!(Limit::items(150).reached(2, 200));
+    }
+}
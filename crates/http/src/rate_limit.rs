@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, TimeZone, Utc};
+
+/// The resource the REST API counts most requests against.
+pub const CORE: &str = "core";
+/// The resource GraphQL queries are counted against, in points rather than requests.
+pub const GRAPHQL: &str = "graphql";
+
+/// Quota GitHub reports in the `x-ratelimit-*` headers of every response.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RateLimit {
+    pub limit: usize,
+    pub remaining: usize,
+    pub used: usize,
+    /// Epoch seconds when the quota is restored.
+    pub reset: i64,
+    pub resource: String,
+    /// Points the latest GraphQL query cost, as reported in its `rateLimit` field.
+    pub cost: Option<usize>,
+}
+
+impl RateLimit {
+    pub fn from_headers(headers: &BTreeMap<String, String>) -> Option<Self> {
+        let header = |name: &str| headers.get(&format!("x-ratelimit-{name}"));
+        let number = |name: &str| header(name).and_then(|it| it.parse::<usize>().ok());
+
+        Some(RateLimit {
+            limit: number("limit")?,
+            remaining: number("remaining")?,
+            used: number("used").unwrap_or_default(),
+            reset: header("reset").and_then(|it| it.parse().ok()).unwrap_or_default(),
+            resource: header("resource").cloned().unwrap_or_else(|| CORE.to_string()),
+            cost: None,
+        })
+    }
+
+    pub fn reset_at(&self) -> DateTime<Utc> {
+        Utc.timestamp_opt(self.reset, 0).single().unwrap_or_default()
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        self.remaining == 0 && self.reset_at() > Utc::now()
+    }
+}
+
+/// Latest [RateLimit] per resource, shared by every clone of the client.
+#[derive(Clone, Default)]
+pub struct RateLimits(Arc<Mutex<BTreeMap<String, RateLimit>>>);
+
+impl RateLimits {
+    pub fn update(&self, headers: &BTreeMap<String, String>) {
+        if let Some(rate_limit) = RateLimit::from_headers(headers) {
+            self.0.lock().unwrap().insert(rate_limit.resource.clone(), rate_limit);
+        }
+    }
+
+    pub fn set_cost(&self, resource: &str, cost: usize) {
+        if let Some(rate_limit) = self.0.lock().unwrap().get_mut(resource) {
+            rate_limit.cost = Some(cost);
+        }
+    }
+
+    pub fn get(&self, resource: &str) -> Option<RateLimit> {
+        self.0.lock().unwrap().get(resource).cloned()
+    }
+}
+
+#[cfg(test)]
+mod from_headers {
+    use std::collections::BTreeMap;
+
+    use crate::rate_limit::{RateLimit, RateLimits};
+
+    fn headers(headers: &[(&str, &str)]) -> BTreeMap<String, String> {
+        headers.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn all_headers() {
+        let headers = headers(&[
+            ("x-ratelimit-limit", "5000"),
+            ("x-ratelimit-remaining", "4987"),
+            ("x-ratelimit-used", "13"),
+            ("x-ratelimit-reset", "1372700873"),
+            ("x-ratelimit-resource", "graphql"),
+        ]);
+        let rate_limit = RateLimit::from_headers(&headers).unwrap();
+        assert_eq!(rate_limit, RateLimit { limit: 5000, remaining: 4987, used: 13, reset: 1372700873, resource: "graphql".to_string(), cost: None });
+        assert_eq!(rate_limit.reset_at().to_rfc3339(), "2013-07-01T17:47:53+00:00");
+    }
+
+    #[test]
+    fn defaults_to_core() {
+        let headers = headers(&[("x-ratelimit-limit", "60"), ("x-ratelimit-remaining", "59")]);
+        assert_eq!(RateLimit::from_headers(&headers).unwrap().resource, "core");
+    }
+
+    #[test]
+    fn missing() {
+        assert_eq!(RateLimit::from_headers(&BTreeMap::new()), None);
+    }
+
+    #[test]
+    fn shared_between_clones() {
+        let rate_limits = RateLimits::default();
+        rate_limits.clone().update(&headers(&[("x-ratelimit-limit", "5000"), ("x-ratelimit-remaining", "10")]));
+        assert_eq!(rate_limits.get("core").unwrap().remaining, 10);
+    }
+}
@@ -0,0 +1,193 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use ehttp::{Request, Response};
+use serde::{Deserialize, Serialize};
+
+use crate::activity::{Activity, ActivityLog, CacheUse};
+use crate::transport::{clone_request, OnDone, Transport};
+
+/// How often and how patiently failed GETs and GraphQL queries are sent again.
+/// The client leaves out other requests, which may have side effects. Transport failures, `5xx` gateway errors and secondary rate limits are retried,
+/// everything else is handed to the caller as is.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    /// Attempts including the first one, `1` disables retries.
+    pub max_attempts: u32,
+    pub base_delay_ms: u64,
+    /// Longer waits, including a `Retry-After` beyond this, give up instead.
+    pub max_delay_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay_ms: 1_000,
+            max_delay_ms: 60_000,
+        }
+    }
+}
+
+/// GitHub asks to wait at least a minute after a secondary rate limit without `Retry-After`.
+const SECONDARY_RATE_LIMIT_DELAY: Duration = Duration::from_secs(60);
+
+impl RetryPolicy {
+    pub fn disabled() -> Self {
+        RetryPolicy { max_attempts: 1, ..Default::default() }
+    }
+
+    /// How long to wait before sending the request again, `None` when `result` is final.
+    /// `random` in `[0, 1)` spreads retries from many requests failing at once.
+    pub(crate) fn delay(&self, attempt: u32, result: &ehttp::Result<Response>, random: f64) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+
+        let delay = match result {
+            Err(_) => self.backoff(attempt, random),
+            Ok(response) => match response.status {
+                500 | 502 | 503 | 504 => retry_after(response).unwrap_or_else(|| self.backoff(attempt, random)),
+                403 | 429 if is_secondary_rate_limit(response) => retry_after(response)
+                    .unwrap_or_else(|| self.backoff(attempt, random).max(SECONDARY_RATE_LIMIT_DELAY)),
+                _ => return None,
+            },
+        };
+
+        (delay <= Duration::from_millis(self.max_delay_ms)).then_some(delay)
+    }
+
+    /// Exponential backoff with jitter, between half and all of `base * 2^(attempt - 1)`.
+    fn backoff(&self, attempt: u32, random: f64) -> Duration {
+        let exponential = self.base_delay_ms.saturating_mul(1 << attempt.saturating_sub(1).min(16));
+        let capped = exponential.min(self.max_delay_ms) as f64;
+        Duration::from_millis((capped * (0.5 + random / 2.0)) as u64)
+    }
+}
+
+fn retry_after(response: &Response) -> Option<Duration> {
+    response.headers.get("retry-after")
+        .and_then(|seconds| seconds.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+/// Secondary limits are not about the hourly quota, which is still left.
+fn is_secondary_rate_limit(response: &Response) -> bool {
+    let quota_left = response.headers.get("x-ratelimit-remaining").map(String::as_str) != Some("0");
+    let mentioned = response.text().is_some_and(|body| body.contains("secondary rate limit"));
+    quota_left && (response.headers.contains_key("retry-after") || mentioned)
+}
+
+fn random() -> f64 {
+    Utc::now().timestamp_subsec_nanos() as f64 / 1_000_000_000.0
+}
+
+/// Sends `request`, retrying it according to `policy` before calling `on_done`.
+/// Every attempt that is sent again is recorded in `activity`, the last one is left to the caller.
+pub(crate) fn fetch(transport: Arc<dyn Transport>, policy: RetryPolicy, activity: ActivityLog, request: Request, attempt: u32, on_done: OnDone) {
+    let retry = clone_request(&request);
+    let waiter = transport.clone();
+    let started = Utc::now();
+    transport.fetch(request, Box::new(move |result| {
+        match policy.delay(attempt, &result, random()) {
+            None => on_done(result),
+            Some(delay) => {
+                let cache_use = match retry.method == "GET" {
+                    true => CacheUse::Miss,
+                    false => CacheUse::Uncached,
+                };
+                activity.record(Activity::new(&retry.method, &retry.url, started, cache_use).retried(&result, delay));
+                let transport = waiter.clone();
+                waiter.after(delay, Box::new(move || fetch(transport, policy, activity, retry, attempt + 1, on_done)));
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod policy {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use ehttp::{Request, Response};
+
+    use crate::activity::ActivityLog;
+    use crate::retry::{fetch, RetryPolicy};
+    use crate::transport::{response, FakeTransport};
+
+    fn run(policy: RetryPolicy, request: Request, results: Vec<ehttp::Result<Response>>) -> (Arc<FakeTransport>, ehttp::Result<Response>) {
+        let transport = Arc::new(results.into_iter().fold(FakeTransport::default(), |transport, result| transport.respond_with("*", result)));
+        let outcome = Arc::new(Mutex::new(None));
+        let _outcome = outcome.clone();
+        fetch(transport.clone(), policy, ActivityLog::default(), request, 1, Box::new(move |result| *_outcome.lock().unwrap() = Some(result)));
+        let result = outcome.lock().unwrap().take().expect("on_done was not called");
+        (transport, result)
+    }
+
+    #[test]
+    fn retries_gateway_errors() {
+        let (transport, result) = run(RetryPolicy::default(), Request::get("some.url"), vec![
+            Ok(response(502, &[], "")),
+            Ok(response(503, &[], "")),
+            Ok(response(200, &[], "[]")),
+        ]);
+        assert_eq!(result.unwrap().status, 200);
+        assert_eq!(transport.requests().len(), 3);
+    }
+
+    #[test]
+    fn gives_up_after_max_attempts() {
+        let (transport, result) = run(RetryPolicy::default(), Request::get("some.url"), vec![
+            Err("connection reset".to_string()),
+            Err("connection reset".to_string()),
+            Err("connection reset".to_string()),
+        ]);
+        assert_eq!(result.unwrap_err(), "connection reset");
+        assert_eq!(transport.requests().len(), 3);
+    }
+
+    #[test]
+    fn honours_retry_after() {
+        let (transport, result) = run(RetryPolicy::default(), Request::get("some.url"), vec![
+            Ok(response(403, &[("retry-after", "30"), ("x-ratelimit-remaining", "4000")], r#"{"message":"You have exceeded a secondary rate limit."}"#)),
+            Ok(response(200, &[], "[]")),
+        ]);
+        assert!(result.is_ok());
+        assert_eq!(transport.delays(), vec![Duration::from_secs(30)]);
+    }
+
+    #[test]
+    fn does_not_retry_client_errors() {
+        let (transport, result) = run(RetryPolicy::default(), Request::get("some.url"), vec![
+            Ok(response(404, &[], r#"{"message":"Not Found"}"#)),
+        ]);
+        assert_eq!(result.unwrap().status, 404);
+        assert_eq!(transport.requests().len(), 1);
+    }
+
+    #[test]
+    fn does_not_retry_exhausted_quota() {
+        let (transport, _) = run(RetryPolicy::default(), Request::get("some.url"), vec![
+            Ok(response(403, &[("x-ratelimit-remaining", "0")], r#"{"message":"API rate limit exceeded"}"#)),
+        ]);
+        assert_eq!(transport.requests().len(), 1);
+    }
+
+    #[test]
+    fn disabled() {
+        let (transport, _) = run(RetryPolicy::disabled(), Request::get("some.url"), vec![
+            Ok(response(502, &[], "")),
+        ]);
+        assert_eq!(transport.requests().len(), 1);
+    }
+
+    #[test]
+    fn exponential_backoff_with_jitter() {
+        let policy = RetryPolicy { max_attempts: 10, base_delay_ms: 1_000, max_delay_ms: 5_000 };
+        assert_eq!(policy.backoff(1, 0.0), Duration::from_millis(500));
+        assert_eq!(policy.backoff(2, 0.999_999), Duration::from_millis(1_999));
+        assert_eq!(policy.backoff(3, 0.0), Duration::from_millis(2_000));
+        assert_eq!(policy.backoff(9, 0.0), Duration::from_millis(2_500));
+    }
+}
@@ -0,0 +1,163 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// GitHub asks clients to avoid concurrent requests, a few at a time keeps refreshes quick
+/// without tripping the abuse detection.
+pub const DEFAULT_MAX_IN_FLIGHT: usize = 4;
+
+/// Lane a request waits in, every queued [Priority::Interactive] request starts before any
+/// [Priority::Background] one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Priority {
+    /// Requested by clicking something, the user is waiting for it.
+    #[default]
+    Interactive,
+    /// Nobody is waiting, e.g. automatic refreshes.
+    Background,
+}
+
+type Job = Box<dyn FnOnce(Slot) + Send>;
+
+struct Queue {
+    max_in_flight: usize,
+    in_flight: usize,
+    interactive: VecDeque<Job>,
+    background: VecDeque<Job>,
+}
+
+/// Queue that starts at most `max_in_flight` jobs at a time, shared by every clone.
+#[derive(Clone)]
+pub struct Scheduler(Arc<Mutex<Queue>>);
+
+/// Held by a running job, the next job starts when it is dropped.
+pub struct Slot(Scheduler);
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Scheduler(Arc::new(Mutex::new(Queue {
+            max_in_flight: DEFAULT_MAX_IN_FLIGHT,
+            in_flight: 0,
+            interactive: VecDeque::new(),
+            background: VecDeque::new(),
+        })))
+    }
+}
+
+impl Scheduler {
+    pub fn set_max_in_flight(&self, max_in_flight: usize) {
+        self.0.lock().unwrap().max_in_flight = max_in_flight.max(1);
+        self.dispatch();
+    }
+
+    /// Runs `job` once a slot is free. The job keeps the [Slot] until its request is done.
+    pub fn submit(&self, priority: Priority, job: impl FnOnce(Slot) + Send + 'static) {
+        {
+            let mut queue = self.0.lock().unwrap();
+            match priority {
+                Priority::Interactive => queue.interactive.push_back(Box::new(job)),
+                Priority::Background => queue.background.push_back(Box::new(job)),
+            }
+        }
+        self.dispatch();
+    }
+
+    /// Jobs waiting for a slot.
+    pub fn queued(&self) -> usize {
+        let queue = self.0.lock().unwrap();
+        queue.interactive.len() + queue.background.len()
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.0.lock().unwrap().in_flight
+    }
+
+    fn dispatch(&self) {
+        loop {
+            let job = {
+                let mut queue = self.0.lock().unwrap();
+                if queue.in_flight >= queue.max_in_flight {
+                    return;
+                }
+                let Some(job) = queue.interactive.pop_front().or_else(|| queue.background.pop_front()) else {
+                    return;
+                };
+                queue.in_flight += 1;
+                job
+            };
+            job(Slot(self.clone()));
+        }
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.0.lock().unwrap().in_flight -= 1;
+        self.0.dispatch();
+    }
+}
+
+#[cfg(test)]
+mod queue {
+    use std::sync::{Arc, Mutex};
+
+    use crate::scheduler::{Priority, Scheduler, Slot};
+
+    /// Submits a job that keeps its slot until released by the test.
+    fn hold(scheduler: &Scheduler, priority: Priority, name: &'static str, started: &Arc<Mutex<Vec<&'static str>>>, held: &Arc<Mutex<Vec<Slot>>>) {
+        let started = started.clone();
+        let held = held.clone();
+        scheduler.submit(priority, move |slot| {
+            started.lock().unwrap().push(name);
+            held.lock().unwrap().push(slot);
+        });
+    }
+
+    #[test]
+    fn bounded() {
+        let scheduler = Scheduler::default();
+        scheduler.set_max_in_flight(2);
+        let started = Arc::new(Mutex::new(vec![]));
+        let held = Arc::new(Mutex::new(vec![]));
+
+        for name in ["a", "b", "c", "d"] {
+            hold(&scheduler, Priority::Interactive, name, &started, &held);
+        }
+        assert_eq!(*started.lock().unwrap(), vec!["a", "b"]);
+        assert_eq!(scheduler.in_flight(), 2);
+        assert_eq!(scheduler.queued(), 2);
+
+        let slot = held.lock().unwrap().remove(0);
+        drop(slot);
+        assert_eq!(*started.lock().unwrap(), vec!["a", "b", "c"]);
+        assert_eq!(scheduler.queued(), 1);
+    }
+
+    #[test]
+    fn interactive_before_background() {
+        let scheduler = Scheduler::default();
+        scheduler.set_max_in_flight(1);
+        let started = Arc::new(Mutex::new(vec![]));
+        let held = Arc::new(Mutex::new(vec![]));
+
+        hold(&scheduler, Priority::Background, "running", &started, &held);
+        hold(&scheduler, Priority::Background, "background", &started, &held);
+        hold(&scheduler, Priority::Interactive, "clicked", &started, &held);
+
+        let slot = held.lock().unwrap().remove(0);
+        drop(slot);
+        assert_eq!(*started.lock().unwrap(), vec!["running", "clicked"]);
+    }
+
+    #[test]
+    fn dropped_slot_releases() {
+        let scheduler = Scheduler::default();
+        scheduler.set_max_in_flight(1);
+        let runs = Arc::new(Mutex::new(0));
+        for _ in 0..3 {
+            let runs = runs.clone();
+            scheduler.submit(Priority::Interactive, move |_slot| *runs.lock().unwrap() += 1);
+        }
+        assert_eq!(*runs.lock().unwrap(), 3);
+        assert_eq!(scheduler.in_flight(), 0);
+    }
+}
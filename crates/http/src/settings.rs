@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+
+pub const GITHUB_API_URL: &str = "https://api.github.com";
+const GITHUB_WEB_URL: &str = "https://github.com";
+
+/// Which GitHub the dashboard talks to, github.com or a GitHub Enterprise Server,
+/// and which organisation it looks at.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct Settings {
+    pub org: String,
+    /// REST API root, `https://HOST/api/v3` on GitHub Enterprise Server.
+    pub api_url: String,
+    /// Client id of the OAuth or GitHub App used by "Sign in with GitHub", which needs the device flow enabled.
+    pub oauth_client_id: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            org: "navikt".to_string(),
+            api_url: GITHUB_API_URL.to_string(),
+            oauth_client_id: String::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// `path` on the REST API, e.g. `/orgs/navikt/teams`.
+    pub fn api(&self, path: &str) -> String {
+        format!("{}{path}", self.api_url.trim_end_matches('/'))
+    }
+
+    /// GraphQL endpoint, `https://HOST/api/graphql` on GitHub Enterprise Server.
+    pub fn graphql_url(&self) -> String {
+        let api_url = self.api_url.trim_end_matches('/');
+        match api_url.strip_suffix("/api/v3") {
+            Some(host) => format!("{host}/api/graphql"),
+            None => format!("{api_url}/graphql"),
+        }
+    }
+
+    /// Root of the web interface belonging to the API.
+    pub fn web_url(&self) -> String {
+        let api_url = self.api_url.trim_end_matches('/');
+        if api_url == GITHUB_API_URL {
+            return GITHUB_WEB_URL.to_string();
+        }
+        api_url.strip_suffix("/api/v3").unwrap_or(api_url).to_string()
+    }
+
+    /// `path` on the web interface, e.g. `/orgs/navikt/teams/aap/members`.
+    pub fn web(&self, path: &str) -> String {
+        format!("{}{path}", self.web_url())
+    }
+}
+
+#[cfg(test)]
+mod urls {
+    use crate::settings::Settings;
+
+    fn settings(api_url: &str) -> Settings {
+        Settings { api_url: api_url.to_string(), ..Settings::default() }
+    }
+
+    #[test]
+    fn github_com() {
+        let settings = Settings::default();
+        assert_eq!(settings.api("/orgs/navikt/teams/aap"), "https://api.github.com/orgs/navikt/teams/aap");
+        assert_eq!(settings.web("/orgs/navikt/teams/aap/members"), "https://github.com/orgs/navikt/teams/aap/members");
+        assert_eq!(settings.graphql_url(), "https://api.github.com/graphql");
+    }
+
+    #[test]
+    fn enterprise_server() {
+        let settings = settings("https://github.example.com/api/v3/");
+        assert_eq!(settings.api("/user"), "https://github.example.com/api/v3/user");
+        assert_eq!(settings.web_url(), "https://github.example.com");
+        assert_eq!(settings.graphql_url(), "https://github.example.com/api/graphql");
+    }
+
+    #[test]
+    fn missing_fields_use_defaults() {
+        let settings = serde_json::from_str::<Settings>(r#"{"org":"octo-org"}"#).unwrap();
+        assert_eq!(settings.org, "octo-org");
+        assert_eq!(settings.api_url, "https://api.github.com");
+    }
+}
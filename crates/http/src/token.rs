@@ -0,0 +1,103 @@
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use ehttp::Response;
+use serde::Deserialize;
+
+use crate::error::GitHubError;
+
+/// Who a token belongs to and what it may do, as reported by `GET /user`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TokenInfo {
+    pub login: String,
+    /// Scopes of a classic token, `None` for fine-grained tokens, which have permissions instead.
+    pub scopes: Option<Vec<String>>,
+    /// Tokens without an expiry have no date.
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize)]
+struct User {
+    login: String,
+}
+
+impl TokenInfo {
+    pub fn from_response(response: &Response) -> Result<Self, GitHubError> {
+        if !response.ok {
+            return Err(GitHubError::from_response(response));
+        }
+
+        let user = serde_json::from_slice::<User>(&response.bytes).map_err(|e| GitHubError::Deserialize(e.to_string()))?;
+        let scopes = response.headers.get("x-oauth-scopes").map(|scopes| {
+            scopes.split(',').map(str::trim).filter(|scope| !scope.is_empty()).map(str::to_string).collect()
+        });
+        let expires_at = response.headers.get("github-authentication-token-expiration").and_then(|it| parse_expiration(it));
+
+        Ok(TokenInfo { login: user.login, scopes, expires_at })
+    }
+
+    /// Whether a classic token has `scope`, directly or through the scope containing it,
+    /// e.g. `repo` covers `repo:status` and `admin:org` covers `read:org`.
+    /// `None` for fine-grained tokens, whose permissions can only be found by trying.
+    pub fn has_scope(&self, scope: &str) -> Option<bool> {
+        let scopes = self.scopes.as_ref()?;
+        let implies = |granted: &str| match scope.split_once(':') {
+            Some(("read", resource)) => granted == format!("write:{resource}") || granted == format!("admin:{resource}"),
+            Some(("write", resource)) => granted == format!("admin:{resource}"),
+            Some((parent, _)) => granted == parent,
+            None => false,
+        };
+        Some(scopes.iter().any(|granted| granted == scope || implies(granted)))
+    }
+}
+
+/// GitHub sends e.g. `2023-03-01 12:00:00 UTC`, or the same with an offset like `+0100`.
+fn parse_expiration(value: &str) -> Option<DateTime<Utc>> {
+    match value.strip_suffix(" UTC") {
+        Some(utc) => NaiveDateTime::parse_from_str(utc, "%Y-%m-%d %H:%M:%S").ok().map(|it| Utc.from_utc_datetime(&it)),
+        None => DateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S %z").ok().map(|it| it.with_timezone(&Utc)),
+    }
+}
+
+#[cfg(test)]
+mod token_info {
+    use crate::error::GitHubError;
+    use crate::token::TokenInfo;
+    use crate::transport::response;
+
+    #[test]
+    fn classic() {
+        let response = response(200, &[
+            ("x-oauth-scopes", "repo, read:org, workflow"),
+            ("github-authentication-token-expiration", "2023-03-01 12:00:00 UTC"),
+        ], r#"{"login":"octocat","id":1}"#);
+        let info = TokenInfo::from_response(&response).unwrap();
+        assert_eq!(info.login, "octocat");
+        assert_eq!(info.scopes, Some(vec!["repo".to_string(), "read:org".to_string(), "workflow".to_string()]));
+        assert_eq!(info.expires_at.unwrap().to_rfc3339(), "2023-03-01T12:00:00+00:00");
+    }
+
+    #[test]
+    fn fine_grained() {
+        let response = response(200, &[("github-authentication-token-expiration", "2023-03-01 13:00:00 +0100")], r#"{"login":"octocat"}"#);
+        let info = TokenInfo::from_response(&response).unwrap();
+        assert_eq!(info.scopes, None);
+        assert_eq!(info.has_scope("repo"), None);
+        assert_eq!(info.expires_at.unwrap().to_rfc3339(), "2023-03-01T12:00:00+00:00");
+    }
+
+    #[test]
+    fn scope_hierarchy() {
+        let info = TokenInfo { login: String::default(), scopes: Some(vec!["repo".to_string(), "admin:org".to_string()]), expires_at: None };
+        assert_eq!(info.has_scope("repo:status"), Some(true));
+        assert_eq!(info.has_scope("read:org"), Some(true));
+        assert_eq!(info.has_scope("workflow"), Some(false));
+
+        let without = TokenInfo { scopes: Some(vec![]), ..info };
+        assert_eq!(without.has_scope("repo"), Some(false));
+    }
+
+    #[test]
+    fn bad_credentials() {
+        let response = response(401, &[], r#"{"message":"Bad credentials"}"#);
+        assert_eq!(TokenInfo::from_response(&response), Err(GitHubError::Status { status: 401, message: "Bad credentials".to_string() }));
+    }
+}
@@ -0,0 +1,207 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use ehttp::{Request, Response};
+
+pub type OnDone = Box<dyn FnOnce(ehttp::Result<Response>) + Send>;
+
+/// What the client needs from the outside world: sending requests and waiting.
+/// [Ehttp] is the default, [FakeTransport] answers from memory for tests.
+pub trait Transport: Send + Sync {
+    fn fetch(&self, request: Request, on_done: OnDone);
+
+    /// Calls `f` once `delay` has passed, without blocking the UI thread.
+    fn after(&self, delay: Duration, f: Box<dyn FnOnce() + Send>);
+}
+
+/// Sends requests with [ehttp::fetch].
+pub struct Ehttp;
+
+impl Transport for Ehttp {
+    fn fetch(&self, request: Request, on_done: OnDone) {
+        ehttp::fetch(request, on_done)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn after(&self, delay: Duration, f: Box<dyn FnOnce() + Send>) {
+        std::thread::spawn(move || {
+            std::thread::sleep(delay);
+            f()
+        });
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn after(&self, delay: Duration, f: Box<dyn FnOnce() + Send>) {
+        use wasm_bindgen::JsCast;
+
+        let callback = wasm_bindgen::closure::Closure::once_into_js(move || f());
+        if let Some(window) = web_sys::window() {
+            let _ = window.set_timeout_with_callback_and_timeout_and_arguments_0(
+                callback.unchecked_ref(),
+                delay.as_millis() as i32,
+            );
+        }
+    }
+}
+
+/// [Request] is not `Clone`, but has to be sent again on retries.
+pub(crate) fn clone_request(request: &Request) -> Request {
+    Request {
+        method: request.method.clone(),
+        url: request.url.clone(),
+        body: request.body.clone(),
+        headers: request.headers.clone(),
+    }
+}
+
+/// Answers requests with canned responses instead of going to the network.
+///
+/// Routes are matched in the order they were added, against the full url,
+/// where `*` in a pattern matches any run of characters.
+/// A route with several responses hands them out in order and keeps repeating the last.
+/// Unmatched urls get a `404`. Waiting is skipped, so everything completes synchronously.
+#[derive(Default)]
+pub struct FakeTransport {
+    routes: Mutex<Vec<(String, VecDeque<ehttp::Result<Response>>)>>,
+    requests: Mutex<Vec<String>>,
+    delays: Mutex<Vec<Duration>>,
+}
+
+impl FakeTransport {
+    /// Answers urls matching `pattern` with `status` and a JSON `body`.
+    pub fn respond(self, pattern: &str, status: u16, body: &str) -> Self {
+        self.respond_with(pattern, Ok(response(status, &[], body)))
+    }
+
+    pub fn respond_with(self, pattern: &str, result: ehttp::Result<Response>) -> Self {
+        {
+            let mut routes = self.routes.lock().unwrap();
+            match routes.iter_mut().find(|(existing, _)| existing == pattern) {
+                Some((_, results)) => results.push_back(result),
+                None => routes.push((pattern.to_string(), VecDeque::from([result]))),
+            }
+        }
+        self
+    }
+
+    /// Urls requested so far, in order.
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Waits that were asked for, e.g. by retries.
+    pub fn delays(&self) -> Vec<Duration> {
+        self.delays.lock().unwrap().clone()
+    }
+
+    fn answer(&self, url: &str) -> ehttp::Result<Response> {
+        let mut routes = self.routes.lock().unwrap();
+        match routes.iter_mut().find(|(pattern, _)| matches(pattern, url)) {
+            Some((_, results)) if results.len() > 1 => results.pop_front().unwrap(),
+            Some((_, results)) => results.front().cloned().unwrap(),
+            None => Ok(response(404, &[], r#"{"message":"Not Found"}"#)),
+        }
+    }
+}
+
+impl Transport for FakeTransport {
+    fn fetch(&self, request: Request, on_done: OnDone) {
+        self.requests.lock().unwrap().push(request.url.clone());
+        let mut result = self.answer(&request.url);
+        if let Ok(response) = &mut result {
+            response.url = request.url;
+        }
+        on_done(result)
+    }
+
+    fn after(&self, delay: Duration, f: Box<dyn FnOnce() + Send>) {
+        self.delays.lock().unwrap().push(delay);
+        f()
+    }
+}
+
+/// A response as GitHub would send it, `headers` are expected in lowercase.
+pub fn response(status: u16, headers: &[(&str, &str)], body: &str) -> Response {
+    Response {
+        url: String::default(),
+        ok: (200..300).contains(&status),
+        status,
+        status_text: String::default(),
+        bytes: body.as_bytes().to_vec(),
+        headers: headers.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect::<BTreeMap<_, _>>(),
+    }
+}
+
+/// Glob match where `*` matches any run of characters.
+fn matches(pattern: &str, url: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = url.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts = parts.collect::<Vec<_>>();
+    match parts.split_last() {
+        None => rest.is_empty(),
+        Some((last, middle)) => {
+            for part in middle {
+                match rest.find(part) {
+                    Some(index) => rest = &rest[index + part.len()..],
+                    None => return false,
+                }
+            }
+            rest.ends_with(last)
+        }
+    }
+}
+
+#[cfg(test)]
+mod fake {
+    use ehttp::Request;
+
+    use crate::transport::{matches, FakeTransport, Transport};
+
+    fn status(transport: &FakeTransport, url: &str) -> u16 {
+        let status = std::sync::Arc::new(std::sync::Mutex::new(0));
+        let _status = status.clone();
+        transport.fetch(Request::get(url), Box::new(move |result| *_status.lock().unwrap() = result.unwrap().status));
+        let status = *status.lock().unwrap();
+        status
+    }
+
+    #[test]
+    fn glob() {
+        assert!(matches("https://api.github.com/repos/*/pulls*", "https://api.github.com/repos/navikt/aap/pulls?per_page=100"));
+        assert!(matches("*/environments*", "https://api.github.com/repos/navikt/aap/environments?per_page=100"));
+        assert!(matches("https://some.url", "https://some.url"));
+        assert!(!matches("https://some.url", "https://some.url/more"));
+        assert!(!matches("*/pulls", "https://api.github.com/repos/navikt/aap/pulls?per_page=100"));
+    }
+
+    #[test]
+    fn first_matching_route() {
+        let transport = FakeTransport::default()
+            .respond("*/specific", 200, "[]")
+            .respond("*", 500, "");
+        assert_eq!(status(&transport, "https://some.url/specific"), 200);
+        assert_eq!(status(&transport, "https://some.url/other"), 500);
+    }
+
+    #[test]
+    fn responses_in_order_then_last_repeats() {
+        let transport = FakeTransport::default()
+            .respond("*", 502, "")
+            .respond("*", 200, "[]");
+        assert_eq!(status(&transport, "https://some.url"), 502);
+        assert_eq!(status(&transport, "https://some.url"), 200);
+        assert_eq!(status(&transport, "https://some.url"), 200);
+    }
+
+    #[test]
+    fn unmatched_is_not_found() {
+        let transport = FakeTransport::default();
+        assert_eq!(status(&transport, "https://some.url"), 404);
+        assert_eq!(transport.requests(), vec!["https://some.url"]);
+    }
+}
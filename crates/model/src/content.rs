@@ -0,0 +1,39 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::{Deserialize, Serialize};
+
+/// A file from the contents API.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Content {
+    pub path: String,
+    pub encoding: String,
+    pub content: String,
+}
+
+impl Content {
+    /// The file as text, GitHub sends it base64 encoded and wrapped at 60 characters.
+    pub fn decoded(&self) -> Result<String, String> {
+        if self.encoding != "base64" {
+            return Err(format!("Unsupported encoding {} of {}", self.encoding, self.path));
+        }
+
+        let content = self.content.split_whitespace().collect::<String>();
+        let bytes = STANDARD.decode(content).map_err(|e| format!("Decoding {} failed: {e}", self.path))?;
+        String::from_utf8(bytes).map_err(|e| format!("{} is not text: {e}", self.path))
+    }
+}
+
+#[cfg(test)]
+mod decoded {
+    use crate::content::Content;
+
+    #[test]
+    fn base64_with_line_breaks() {
+        let content = Content {
+            path: ".github/workflows/deploy.yml".to_string(),
+            encoding: "base64".to_string(),
+            content: "bmFtZTogRGVw\nbG95Cg==\n".to_string(),
+        };
+        assert_eq!(content.decoded().unwrap(), "name: Deploy\n");
+    }
+}
@@ -1,3 +1,6 @@
+use std::fmt::{Display, Formatter};
+
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -6,21 +9,21 @@ pub struct Deployment {
     pub id: i64,
     pub task: String,
     pub environment: String,
-    pub created_at: String,
-    pub updated_at: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
     pub statuses_url: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct Status {
     pub url: String,
     pub id: i64,
     pub state: State,
-    description: String,
+    pub(crate) description: String,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
-#[serde(rename_all = "snake_case")]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(from = "String", into = "String")]
 pub enum State {
     Error,
     Failure,
@@ -29,11 +32,44 @@ pub enum State {
     Success,
     Queued,
     InProgress,
+    /// A state GitHub added later, as spelled by GitHub, so the other statuses still deserialize.
+    Unknown(String),
 }
 
-impl ToString for State {
-    fn to_string(&self) -> String {
-        format!("{:?}", self.clone())
+impl From<String> for State {
+    fn from(state: String) -> Self {
+        match state.as_str() {
+            "error" => State::Error,
+            "failure" => State::Failure,
+            "inactive" => State::Inactive,
+            "pending" => State::Pending,
+            "success" => State::Success,
+            "queued" => State::Queued,
+            "in_progress" => State::InProgress,
+            _ => State::Unknown(state),
+        }
+    }
+}
+
+impl From<State> for String {
+    fn from(state: State) -> Self {
+        state.to_string()
+    }
+}
+
+/// As spelled by GitHub.
+impl Display for State {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            State::Error => "error",
+            State::Failure => "failure",
+            State::Inactive => "inactive",
+            State::Pending => "pending",
+            State::Success => "success",
+            State::Queued => "queued",
+            State::InProgress => "in_progress",
+            State::Unknown(state) => state,
+        })
     }
 }
 
@@ -47,7 +83,10 @@ impl Status {
 }
 
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod deployment {
+    use chrono::{TimeZone, Utc};
+
     use crate::deployment::Deployment;
 
     #[test]
@@ -58,8 +97,8 @@ mod deployment {
         assert_eq!(deployment.id, 1);
         assert_eq!(deployment.task, "deploy");
         assert_eq!(deployment.environment, "production");
-        assert_eq!(deployment.created_at, "2012-07-20T01:19:13Z");
-        assert_eq!(deployment.updated_at, "2012-07-20T01:19:13Z");
+        assert_eq!(deployment.created_at, Utc.with_ymd_and_hms(2012, 7, 20, 1, 19, 13).unwrap());
+        assert_eq!(deployment.updated_at, Utc.with_ymd_and_hms(2012, 7, 20, 1, 19, 13).unwrap());
         assert_eq!(deployment.statuses_url, "https://api.github.com/repos/octocat/example/deployments/1/statuses");
     }
 
@@ -70,8 +109,8 @@ mod deployment {
             id: 1,
             task: "deploy".to_string(),
             environment: "production".to_string(),
-            created_at: "2012-07-20T01:19:13Z".to_string(),
-            updated_at: "2012-07-20T01:19:13Z".to_string(),
+            created_at: Utc.with_ymd_and_hms(2012, 7, 20, 1, 19, 13).unwrap(),
+            updated_at: Utc.with_ymd_and_hms(2012, 7, 20, 1, 19, 13).unwrap(),
             statuses_url: "https://api.github.com/repos/octocat/example/deployments/1/statuses".to_string(),
         };
 
@@ -79,4 +118,52 @@ mod deployment {
         let expected = r#"{"url":"https://api.github.com/repos/octocat/example/deployments/1","id":1,"task":"deploy","environment":"production","created_at":"2012-07-20T01:19:13Z","updated_at":"2012-07-20T01:19:13Z","statuses_url":"https://api.github.com/repos/octocat/example/deployments/1/statuses"}"#;
         assert_eq!(json, expected.to_string())
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn invalid_timestamp() {
+        let json = r#"{"url":"","id":1,"task":"deploy","environment":"production","created_at":"yesterday","updated_at":"2012-07-20T01:19:13Z","statuses_url":""}"#;
+        assert!(serde_json::from_str::<Deployment>(json).is_err());
+    }
+}
+
+#[cfg(test)]
+mod state {
+    use crate::deployment::{State, Status};
+
+    /// The example deployment status from GitHub's REST documentation, with `state` replaced.
+    fn documented(state: &str) -> String {
+        format!(r#"{{"url":"https://api.github.com/repos/octocat/example/deployments/42/statuses/1","id":1,"node_id":"MDE2OkRlcGxveW1lbnRTdGF0dXMx","state":"{state}","creator":{{"login":"octocat","id":1,"node_id":"MDQ6VXNlcjE=","avatar_url":"https://github.com/images/error/octocat_happy.gif","gravatar_id":"","url":"https://api.github.com/users/octocat","html_url":"https://github.com/octocat","type":"User","site_admin":false}},"description":"Deployment finished successfully.","environment":"production","target_url":"https://example.com/deployment/42/output","created_at":"2012-07-20T01:19:13Z","updated_at":"2012-07-20T01:19:13Z","deployment_url":"https://api.github.com/repos/octocat/example/deployments/42","repository_url":"https://api.github.com/repos/octocat/example","environment_url":"https://test-branch.lab.acme.com","log_url":"https://example.com/deployment/42/output"}}"#)
+    }
+
+    #[test]
+    fn every_documented_state() {
+        let states = [
+            ("error", State::Error),
+            ("failure", State::Failure),
+            ("inactive", State::Inactive),
+            ("pending", State::Pending),
+            ("success", State::Success),
+            ("queued", State::Queued),
+            ("in_progress", State::InProgress),
+        ];
+        states.into_iter().for_each(|(spelling, state)| {
+            let status = serde_json::from_str::<Status>(&documented(spelling)).unwrap();
+            assert_eq!(status.state, state);
+            assert_eq!(status.state.to_string(), spelling);
+
+            let json = serde_json::to_string(&status).unwrap();
+            assert!(json.contains(&format!(r#""state":"{spelling}""#)), "{json}");
+            assert_eq!(serde_json::from_str::<Status>(&json).unwrap(), status);
+        });
+    }
+
+    #[test]
+    fn unknown_state_keeps_the_others() {
+        let json = format!("[{},{}]", documented("success"), documented("rolling_back"));
+        let statuses = serde_json::from_str::<Vec<Status>>(&json).unwrap();
+        assert_eq!(statuses[0].state, State::Success);
+        assert_eq!(statuses[1].state, State::Unknown("rolling_back".to_string()));
+        assert_eq!(statuses[1].state.to_string(), "rolling_back");
+        assert!(serde_json::to_string(&statuses[1]).unwrap().contains(r#""state":"rolling_back""#));
+    }
+}
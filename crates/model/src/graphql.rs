@@ -0,0 +1,384 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::deployment::{Deployment, State, Status};
+use crate::environment::Environment;
+use crate::pull_request::{Head, PullRequest, Repo, User};
+
+/// Repositories per query, keeps each query well below GitHub's node and timeout limits.
+pub const BATCH_SIZE: usize = 25;
+
+/// Body of a request to the GraphQL endpoint.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Query {
+    pub query: String,
+    pub variables: BTreeMap<String, String>,
+}
+
+/// `rateLimit` as returned next to the data of every query.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimit {
+    pub cost: usize,
+    pub limit: usize,
+    pub remaining: usize,
+    pub reset_at: DateTime<Utc>,
+}
+
+/// Data of a batched query, one entry per repository alias.
+/// A repository that could not be resolved is `null`.
+#[derive(Deserialize, Clone, Debug)]
+pub struct Batch<T> {
+    #[serde(rename = "rateLimit")]
+    pub rate_limit: RateLimit,
+    #[serde(flatten)]
+    pub repositories: BTreeMap<String, Option<T>>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Connection<T> {
+    pub nodes: Vec<T>,
+    /// Only asked for where there may be more than one page.
+    #[serde(default)]
+    pub page_info: PageInfo,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PageInfo {
+    pub has_next_page: bool,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Login {
+    pub login: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PullRequests {
+    pub name: String,
+    pub pull_requests: Connection<PullRequestNode>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PullRequestNode {
+    pub database_id: i64,
+    pub title: String,
+    pub url: String,
+    pub updated_at: DateTime<Utc>,
+    pub author: Option<Login>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Deployments {
+    pub name: String,
+    pub environments: Connection<EnvironmentNode>,
+    pub deployments: Connection<DeploymentNode>,
+}
+
+/// The latest deployment per environment of one repository, one alias per environment.
+#[derive(Deserialize, Clone, Debug)]
+pub struct LatestDeployments {
+    #[serde(rename = "rateLimit")]
+    pub rate_limit: RateLimit,
+    pub repository: Option<BTreeMap<String, Connection<DeploymentNode>>>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvironmentNode {
+    pub database_id: i64,
+    pub name: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DeploymentNode {
+    pub database_id: i64,
+    pub environment: Option<String>,
+    pub task: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub latest_status: Option<DeploymentStatusNode>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct DeploymentStatusNode {
+    /// `SUCCESS`, `IN_PROGRESS` etc.
+    pub state: String,
+    pub description: Option<String>,
+}
+
+impl PullRequests {
+    /// Whether there are more open pull requests than the query fetches.
+    pub fn has_more(&self) -> bool {
+        self.pull_requests.page_info.has_next_page
+    }
+
+    /// The same pull requests as listed by the REST API.
+    pub fn into_pull_requests(self) -> Vec<PullRequest> {
+        let repo = self.name;
+        self.pull_requests.nodes.into_iter()
+            .map(|node| PullRequest {
+                id: node.database_id,
+                url: node.url.clone(),
+                html_url: Some(node.url),
+                title: Some(node.title),
+                user: node.author.map(|author| User { login: author.login }),
+                head: Head { repo: Repo { name: repo.clone() } },
+                updated_at: Some(node.updated_at),
+            })
+            .collect()
+    }
+}
+
+impl Deployments {
+    /// Whether there are more environments than the query fetches.
+    pub fn has_more_environments(&self) -> bool {
+        self.environments.page_info.has_next_page
+    }
+
+    pub fn environments(&self) -> Vec<Environment> {
+        self.environments.nodes.iter()
+            .map(|node| Environment {
+                url: String::default(),
+                id: node.database_id,
+                name: node.name.clone(),
+                html_url: String::default(),
+            })
+            .collect()
+    }
+
+    /// Deployments newest first, with the latest status of those that have one.
+    /// `deployments_url` is the REST listing of the repository, which the deployment urls are built from.
+    pub fn deployments(&self, deployments_url: &str) -> Vec<(Deployment, Option<Status>)> {
+        self.deployments.nodes.iter().map(|node| deployment(node, deployments_url)).collect()
+    }
+
+    /// Environments without a deployment among those fetched, when older deployments were left out.
+    pub fn missing_environments(&self) -> Vec<String> {
+        if !self.deployments.page_info.has_next_page {
+            return vec![];
+        }
+        self.environments.nodes.iter()
+            .filter(|environment| !self.deployments.nodes.iter().any(|node| node.environment.as_ref() == Some(&environment.name)))
+            .map(|environment| environment.name.clone())
+            .collect()
+    }
+}
+
+impl LatestDeployments {
+    /// As [Deployments::deployments].
+    pub fn deployments(&self, deployments_url: &str) -> Vec<(Deployment, Option<Status>)> {
+        self.repository.iter()
+            .flat_map(|environments| environments.values())
+            .flat_map(|connection| connection.nodes.iter())
+            .map(|node| deployment(node, deployments_url))
+            .collect()
+    }
+}
+
+fn deployment(node: &DeploymentNode, deployments_url: &str) -> (Deployment, Option<Status>) {
+    let url = format!("{deployments_url}/{}", node.database_id);
+    let deployment = Deployment {
+        statuses_url: format!("{url}/statuses"),
+        url,
+        id: node.database_id,
+        task: node.task.clone().unwrap_or_default(),
+        environment: node.environment.clone().unwrap_or_default(),
+        created_at: node.created_at,
+        updated_at: node.updated_at,
+    };
+    let status = node.latest_status.as_ref().map(|status| Status {
+        url: String::default(),
+        id: 0,
+        state: state(&status.state),
+        description: status.description.clone().unwrap_or_default(),
+    });
+    (deployment, status)
+}
+
+/// GraphQL spells the REST states in upper case, and has `WAITING` for pending approval.
+fn state(state: &str) -> State {
+    match state {
+        "WAITING" => State::Pending,
+        _ => State::from(state.to_lowercase()),
+    }
+}
+
+const PULL_REQUESTS: &str = "
+fragment Fields on Repository {
+  name
+  pullRequests(states: OPEN, first: 100, orderBy: {field: UPDATED_AT, direction: DESC}) {
+    nodes { databaseId title url updatedAt author { login } }
+    pageInfo { hasNextPage }
+  }
+}";
+
+const DEPLOYMENTS: &str = "
+fragment Fields on Repository {
+  name
+  environments(first: 50) {
+    nodes { databaseId name }
+    pageInfo { hasNextPage }
+  }
+  deployments(first: 100, orderBy: {field: CREATED_AT, direction: DESC}) {
+    nodes { ...DeploymentFields }
+    pageInfo { hasNextPage }
+  }
+}";
+
+const DEPLOYMENT_FIELDS: &str = "
+fragment DeploymentFields on Deployment { databaseId environment task createdAt updatedAt latestStatus { state description } }";
+
+/// Open pull requests of every repository, in batches of [BATCH_SIZE].
+pub fn pull_requests_queries(owner: &str, repositories: &[String]) -> Vec<Query> {
+    batch_queries(owner, repositories, PULL_REQUESTS)
+}
+
+/// Environments and recent deployments with their latest status, in batches of [BATCH_SIZE].
+pub fn deployments_queries(owner: &str, repositories: &[String]) -> Vec<Query> {
+    batch_queries(owner, repositories, &format!("{DEPLOYMENTS}{DEPLOYMENT_FIELDS}"))
+}
+
+/// The latest deployment to each of `environments`, for a repository with more deployments than [deployments_queries] fetches.
+pub fn latest_deployments_query(owner: &str, repository: &str, environments: &[String]) -> Query {
+    let parameters = (0..environments.len()).map(|index| format!(", $e{index}: String!")).collect::<String>();
+    let aliases = (0..environments.len())
+        .map(|index| format!("    e{index}: deployments(environments: [$e{index}], first: 1, orderBy: {{field: CREATED_AT, direction: DESC}}) {{ nodes {{ ...DeploymentFields }} }}\n"))
+        .collect::<String>();
+
+    let mut variables = BTreeMap::from([("owner".to_string(), owner.to_string()), ("name".to_string(), repository.to_string())]);
+    variables.extend(environments.iter().enumerate().map(|(index, environment)| (format!("e{index}"), environment.clone())));
+
+    Query {
+        query: format!("query($owner: String!, $name: String!{parameters}) {{\n  rateLimit {{ cost limit remaining resetAt }}\n  repository(owner: $owner, name: $name) {{\n{aliases}  }}\n}}\n{DEPLOYMENT_FIELDS}"),
+        variables,
+    }
+}
+
+/// Repository names are passed as variables, GraphQL takes care of quoting them.
+fn batch_queries(owner: &str, repositories: &[String], fragment: &str) -> Vec<Query> {
+    repositories
+        .chunks(BATCH_SIZE)
+        .map(|chunk| {
+            let parameters = (0..chunk.len()).map(|index| format!(", $r{index}: String!")).collect::<String>();
+            let aliases = (0..chunk.len())
+                .map(|index| format!("  r{index}: repository(owner: $owner, name: $r{index}) {{ ...Fields }}\n"))
+                .collect::<String>();
+
+            let mut variables = BTreeMap::from([("owner".to_string(), owner.to_string())]);
+            variables.extend(chunk.iter().enumerate().map(|(index, name)| (format!("r{index}"), name.clone())));
+
+            Query {
+                query: format!("query($owner: String!{parameters}) {{\n  rateLimit {{ cost limit remaining resetAt }}\n{aliases}}}\n{fragment}"),
+                variables,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod batch {
+    use crate::deployment::State;
+    use crate::graphql::{latest_deployments_query, state, Batch, BATCH_SIZE, Deployments, LatestDeployments, pull_requests_queries};
+
+    #[test]
+    fn aliases_per_repository() {
+        let queries = pull_requests_queries("navikt", &["aap-api".to_string(), "aap-web".to_string()]);
+        assert_eq!(queries.len(), 1);
+        assert!(queries[0].query.starts_with("query($owner: String!, $r0: String!, $r1: String!)"));
+        assert!(queries[0].query.contains("r0: repository(owner: $owner, name: $r0) { ...Fields }"));
+        assert!(queries[0].query.contains("r1: repository(owner: $owner, name: $r1) { ...Fields }"));
+        assert_eq!(queries[0].variables.get("owner").unwrap(), "navikt");
+        assert_eq!(queries[0].variables.get("r1").unwrap(), "aap-web");
+    }
+
+    #[test]
+    fn names_are_not_part_of_the_query() {
+        let queries = pull_requests_queries("navikt", &[r#"api") { id } x: repository(name: "other"#.to_string()]);
+        assert!(!queries[0].query.contains("other"));
+        assert!(queries[0].variables.get("r0").unwrap().contains("other"));
+    }
+
+    #[test]
+    fn batched() {
+        let repositories = (0..BATCH_SIZE + 1).map(|i| format!("repo-{i}")).collect::<Vec<_>>();
+        assert_eq!(pull_requests_queries("navikt", &repositories).len(), 2);
+    }
+
+    #[test]
+    fn deserialize() {
+        let json = r#"{
+            "rateLimit": { "cost": 1, "limit": 5000, "remaining": 4999, "resetAt": "2023-02-01T11:00:00Z" },
+            "r0": {
+                "name": "aap-api",
+                "environments": { "nodes": [{ "databaseId": 7, "name": "prod-gcp" }] },
+                "deployments": { "nodes": [{ "databaseId": 42, "environment": "prod-gcp", "task": "deploy", "createdAt": "2023-02-01T10:00:00Z", "updatedAt": "2023-02-01T10:05:00Z", "latestStatus": { "state": "SUCCESS", "description": null } }] }
+            },
+            "r1": null
+        }"#;
+        let batch = serde_json::from_str::<Batch<Deployments>>(json).unwrap();
+        assert_eq!(batch.rate_limit.cost, 1);
+        assert_eq!(batch.repositories.len(), 2);
+        let repository = batch.repositories.get("r0").unwrap().as_ref().unwrap();
+        assert_eq!(repository.deployments.nodes[0].latest_status.as_ref().unwrap().state, "SUCCESS");
+        assert!(batch.repositories.get("r1").unwrap().is_none());
+    }
+
+    #[test]
+    fn latest_status() {
+        let json = r#"{
+            "name": "aap-api",
+            "environments": { "nodes": [] },
+            "deployments": { "nodes": [
+                { "databaseId": 2, "environment": "dev", "task": null, "createdAt": "2023-02-01T10:00:00Z", "updatedAt": "2023-02-01T10:00:00Z", "latestStatus": { "state": "IN_PROGRESS", "description": "Rolling out" } },
+                { "databaseId": 1, "environment": "dev", "task": null, "createdAt": "2023-01-31T10:00:00Z", "updatedAt": "2023-01-31T10:00:00Z", "latestStatus": null }
+            ] }
+        }"#;
+        let deployments = serde_json::from_str::<Deployments>(json).unwrap().deployments("https://api.github.com/repos/navikt/aap-api/deployments");
+        assert_eq!(deployments[0].0.id, 2);
+        assert_eq!(deployments[0].0.statuses_url, "https://api.github.com/repos/navikt/aap-api/deployments/2/statuses");
+        assert!(matches!(deployments[0].1.as_ref().unwrap().state, State::InProgress));
+        assert!(deployments[1].1.is_none());
+    }
+
+    #[test]
+    fn missing_environments() {
+        let json = r#"{
+            "name": "aap-api",
+            "environments": { "nodes": [{ "databaseId": 1, "name": "dev" }, { "databaseId": 2, "name": "prod" }] },
+            "deployments": { "nodes": [
+                { "databaseId": 9, "environment": "dev", "task": null, "createdAt": "2023-02-01T10:00:00Z", "updatedAt": "2023-02-01T10:00:00Z", "latestStatus": null }
+            ], "pageInfo": { "hasNextPage": HAS_NEXT_PAGE } }
+        }"#;
+        let deployments = |has_next_page: &str| serde_json::from_str::<Deployments>(&json.replace("HAS_NEXT_PAGE", has_next_page)).unwrap();
+        assert_eq!(deployments("true").missing_environments(), vec!["prod"]);
+        assert!(deployments("false").missing_environments().is_empty(), "prod has never been deployed to");
+
+        let query = latest_deployments_query("navikt", "aap-api", &["prod".to_string()]);
+        assert!(query.query.contains("e0: deployments(environments: [$e0], first: 1"));
+        assert_eq!(query.variables.get("e0").unwrap(), "prod");
+
+        let latest = r#"{
+            "rateLimit": { "cost": 1, "limit": 5000, "remaining": 4998, "resetAt": "2023-02-01T11:00:00Z" },
+            "repository": { "e0": { "nodes": [{ "databaseId": 3, "environment": "prod", "task": "deploy", "createdAt": "2023-01-01T10:00:00Z", "updatedAt": "2023-01-01T10:00:00Z", "latestStatus": { "state": "SUCCESS", "description": null } }] } }
+        }"#;
+        let deployments = serde_json::from_str::<LatestDeployments>(latest).unwrap().deployments("https://api.github.com/repos/navikt/aap-api/deployments");
+        assert_eq!(deployments[0].0.environment, "prod");
+        assert_eq!(deployments[0].1.as_ref().unwrap().state, State::Success);
+    }
+
+    #[test]
+    fn states() {
+        assert_eq!(state("IN_PROGRESS"), State::InProgress);
+        assert_eq!(state("WAITING"), State::Pending);
+        assert_eq!(state("ROLLING_BACK"), State::Unknown("rolling_back".to_string()));
+    }
+}
@@ -3,4 +3,8 @@ pub mod repository;
 pub mod team;
 pub mod workflow;
 pub mod deployment;
-pub mod environment;
\ No newline at end of file
+pub mod environment;
+pub mod graphql;
+pub mod content;
+pub mod workflow_dispatch;
+pub mod workflow_log;
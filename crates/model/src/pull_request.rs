@@ -1,30 +1,31 @@
 use std::string::ToString;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Clone, Default)]
 pub struct PullRequest {
     pub id: i64,
     pub url: String,
-    html_url: Option<String>,
-    title: Option<String>,
-    user: Option<User>,
-    head: Head,
-    updated_at: Option<String>,
+    pub(crate) html_url: Option<String>,
+    pub(crate) title: Option<String>,
+    pub(crate) user: Option<User>,
+    pub(crate) head: Head,
+    pub(crate) updated_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Default)]
-struct Head {
-    repo: Repo,
+pub(crate) struct Head {
+    pub(crate) repo: Repo,
 }
 
 #[derive(Serialize, Deserialize, Clone, Default)]
-struct Repo {
-    name: String,
+pub(crate) struct Repo {
+    pub(crate) name: String,
 }
 
 #[derive(Serialize, Deserialize, Clone, Default)]
-struct User {
-    login: String,
+pub(crate) struct User {
+    pub(crate) login: String,
 }
 
 const UNKNOWN: &str = "unknown";
@@ -33,7 +34,7 @@ impl PullRequest {
     pub fn html(&self) -> String { self.html_url.clone().unwrap_or(self.url.clone()) }
     pub fn title(&self) -> String { self.title.clone().unwrap_or(UNKNOWN.to_string()) }
     pub fn user(&self) -> String { self.user.clone().unwrap_or(User { login: UNKNOWN.to_string() }).login }
-    pub fn updated_at(&self) -> String { self.updated_at.clone().unwrap_or(UNKNOWN.to_string()) }
+    pub fn updated_at(&self) -> Option<DateTime<Utc>> { self.updated_at }
     pub fn repo(&self) -> String { self.head.repo.name.clone() }
 }
 
@@ -12,6 +12,8 @@ pub struct Repository {
     pulls_url: String,
     pub description: Option<String>,
     pub archived: bool,
+    #[serde(default)]
+    pub default_branch: String,
 }
 
 impl Repository {
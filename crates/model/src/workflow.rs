@@ -1,3 +1,6 @@
+use std::fmt::{Display, Formatter};
+
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Clone, Default)]
@@ -21,10 +24,12 @@ pub struct WorkflowRun {
     pub name: Option<String>,
     pub run_attempt: i32,
     pub event: String,
-    pub conclusion: Option<String>,
+    #[serde(default)]
+    pub status: Option<RunStatus>,
+    pub conclusion: Option<Conclusion>,
     pub workflow_id: i64,
     pub html_url: String,
-    pub run_started_at: Option<String>,
+    pub run_started_at: Option<DateTime<Utc>>,
     pub actor: Option<Actor>,
     pub triggering_actor: Option<Actor>,
     pub jobs_url: String,
@@ -37,6 +42,184 @@ pub struct WorkflowRun {
     pub display_title: String,
 }
 
+/// Where a run is, [RunStatus::Completed] once it has a [Conclusion].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(from = "String", into = "String")]
+pub enum RunStatus {
+    Requested,
+    Queued,
+    Pending,
+    /// Waiting for a reviewer to approve a deployment.
+    Waiting,
+    InProgress,
+    Completed,
+    /// Whatever GitHub adds later, as spelled by GitHub.
+    Unknown(String),
+}
+
+/// How a completed run went.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(from = "String", into = "String")]
+pub enum Conclusion {
+    Success,
+    Failure,
+    Neutral,
+    Cancelled,
+    Skipped,
+    TimedOut,
+    ActionRequired,
+    Stale,
+    /// The workflow file could not be run at all, e.g. because it is invalid.
+    StartupFailure,
+    /// Whatever GitHub adds later, as spelled by GitHub.
+    Unknown(String),
+}
+
+impl Conclusion {
+    /// The run, job or step broke, as opposed to being cancelled or skipped.
+    pub fn is_failure(&self) -> bool {
+        matches!(self, Conclusion::Failure | Conclusion::TimedOut | Conclusion::StartupFailure)
+    }
+}
+
+impl From<String> for RunStatus {
+    fn from(status: String) -> Self {
+        match status.as_str() {
+            "requested" => RunStatus::Requested,
+            "queued" => RunStatus::Queued,
+            "pending" => RunStatus::Pending,
+            "waiting" => RunStatus::Waiting,
+            "in_progress" => RunStatus::InProgress,
+            "completed" => RunStatus::Completed,
+            _ => RunStatus::Unknown(status),
+        }
+    }
+}
+
+impl From<RunStatus> for String {
+    fn from(status: RunStatus) -> Self {
+        status.to_string()
+    }
+}
+
+/// As spelled by GitHub.
+impl Display for RunStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            RunStatus::Requested => "requested",
+            RunStatus::Queued => "queued",
+            RunStatus::Pending => "pending",
+            RunStatus::Waiting => "waiting",
+            RunStatus::InProgress => "in_progress",
+            RunStatus::Completed => "completed",
+            RunStatus::Unknown(status) => status,
+        })
+    }
+}
+
+impl From<String> for Conclusion {
+    fn from(conclusion: String) -> Self {
+        match conclusion.as_str() {
+            "success" => Conclusion::Success,
+            "failure" => Conclusion::Failure,
+            "neutral" => Conclusion::Neutral,
+            "cancelled" => Conclusion::Cancelled,
+            "skipped" => Conclusion::Skipped,
+            "timed_out" => Conclusion::TimedOut,
+            "action_required" => Conclusion::ActionRequired,
+            "stale" => Conclusion::Stale,
+            "startup_failure" => Conclusion::StartupFailure,
+            _ => Conclusion::Unknown(conclusion),
+        }
+    }
+}
+
+impl From<Conclusion> for String {
+    fn from(conclusion: Conclusion) -> Self {
+        conclusion.to_string()
+    }
+}
+
+/// As spelled by GitHub.
+impl Display for Conclusion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Conclusion::Success => "success",
+            Conclusion::Failure => "failure",
+            Conclusion::Neutral => "neutral",
+            Conclusion::Cancelled => "cancelled",
+            Conclusion::Skipped => "skipped",
+            Conclusion::TimedOut => "timed_out",
+            Conclusion::ActionRequired => "action_required",
+            Conclusion::Stale => "stale",
+            Conclusion::StartupFailure => "startup_failure",
+            Conclusion::Unknown(conclusion) => conclusion,
+        })
+    }
+}
+
+impl WorkflowRun {
+    /// Re-runs only the failed jobs and whatever depends on them.
+    pub fn rerun_failed_jobs_url(&self) -> String {
+        let run_url = self.rerun_url.strip_suffix("/rerun").unwrap_or(&self.rerun_url);
+        format!("{run_url}/rerun-failed-jobs")
+    }
+}
+
+/// A job of a run, as listed from [WorkflowRun::jobs_url].
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Job {
+    pub id: i64,
+    pub run_id: i64,
+    pub name: String,
+    pub status: Option<RunStatus>,
+    pub conclusion: Option<Conclusion>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub html_url: Option<String>,
+    /// What the job asked for in `runs-on`.
+    #[serde(default)]
+    pub labels: Vec<String>,
+    pub runner_name: Option<String>,
+    #[serde(default)]
+    pub steps: Vec<Step>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Step {
+    pub name: String,
+    pub number: i64,
+    pub status: Option<RunStatus>,
+    pub conclusion: Option<Conclusion>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+impl Job {
+    pub fn duration(&self, now: DateTime<Utc>) -> Option<Duration> {
+        duration(self.started_at, self.completed_at, now)
+    }
+
+    pub fn failed(&self) -> bool {
+        matches!(&self.conclusion, Some(conclusion) if conclusion.is_failure())
+    }
+}
+
+impl Step {
+    pub fn duration(&self, now: DateTime<Utc>) -> Option<Duration> {
+        duration(self.started_at, self.completed_at, now)
+    }
+
+    pub fn failed(&self) -> bool {
+        matches!(&self.conclusion, Some(conclusion) if conclusion.is_failure())
+    }
+}
+
+/// How long it took, or has taken so far while it is still running.
+fn duration(started_at: Option<DateTime<Utc>>, completed_at: Option<DateTime<Utc>>, now: DateTime<Utc>) -> Option<Duration> {
+    Some(completed_at.unwrap_or(now) - started_at?)
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Actor {
     pub name: Option<String>,
@@ -50,4 +233,72 @@ pub struct Actor {
     pub html_url: String,
     #[serde(rename = "type")]
     pub actor_type: String,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod conclusion {
+    use crate::workflow::{Conclusion, RunStatus, WorkflowRun};
+
+    fn run(status: &str, conclusion: &str) -> WorkflowRun {
+        let json = format!(r#"{{"id":1,"name":"build","run_attempt":1,"event":"push","status":{status},"conclusion":{conclusion},"workflow_id":1,"html_url":"","run_started_at":null,"actor":null,"triggering_actor":null,"jobs_url":"","logs_url":"","check_suite_url":"","artifacts_url":"","cancel_url":"","rerun_url":"","workflow_url":"","display_title":"build"}}"#);
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn known() {
+        let run = run(r#""completed""#, r#""timed_out""#);
+        assert_eq!(run.status, Some(RunStatus::Completed));
+        assert_eq!(run.conclusion, Some(Conclusion::TimedOut));
+    }
+
+    #[test]
+    fn running_has_no_conclusion() {
+        let run = run(r#""in_progress""#, "null");
+        assert_eq!(run.status, Some(RunStatus::InProgress));
+        assert_eq!(run.conclusion, None);
+    }
+
+    #[test]
+    fn unknown_is_kept() {
+        let run = run(r#""sleeping""#, r#""postponed""#);
+        assert_eq!(run.status, Some(RunStatus::Unknown("sleeping".to_string())));
+        assert_eq!(run.conclusion, Some(Conclusion::Unknown("postponed".to_string())));
+        assert_eq!(serde_json::to_string(&run.conclusion).unwrap(), r#""postponed""#);
+    }
+
+    #[test]
+    fn round_trip() {
+        let json = serde_json::to_string(&Conclusion::StartupFailure).unwrap();
+        assert_eq!(json, r#""startup_failure""#);
+        assert_eq!(serde_json::from_str::<Conclusion>(&json).unwrap(), Conclusion::StartupFailure);
+    }
+}
+
+#[cfg(test)]
+mod job {
+    use chrono::{Duration, TimeZone, Utc};
+
+    use crate::workflow::{Conclusion, Job, RunStatus};
+
+    /// Abbreviated from the example in GitHub's REST documentation, with a second step still running.
+    const JOB: &str = r#"{"id":399444496,"run_id":29679449,"run_url":"https://api.github.com/repos/octo-org/octo-repo/actions/runs/29679449","node_id":"MDEyOldvcmtmbG93IEpvYjM5OTQ0NDQ5Ng==","head_sha":"f83a356604ae3c5d03e1b46ef4d1ca77d64a90b0","url":"https://api.github.com/repos/octo-org/octo-repo/actions/jobs/399444496","html_url":"https://github.com/octo-org/octo-repo/runs/399444496","status":"in_progress","conclusion":null,"started_at":"2020-01-20T17:42:40Z","completed_at":null,"name":"build","steps":[{"name":"Set up job","status":"completed","conclusion":"success","number":1,"started_at":"2020-01-20T09:42:40.000-08:00","completed_at":"2020-01-20T09:42:41.000-08:00"},{"name":"Run tests","status":"in_progress","conclusion":null,"number":2,"started_at":"2020-01-20T09:42:41.000-08:00","completed_at":null}],"check_run_url":"https://api.github.com/repos/octo-org/octo-repo/check-runs/399444496","labels":["self-hosted","foo","bar"],"runner_id":1,"runner_name":"my runner","runner_group_id":2,"runner_group_name":"my runner group","workflow_name":"CI","head_branch":"main"}"#;
+
+    #[test]
+    fn deserialize() {
+        let job = serde_json::from_str::<Job>(JOB).unwrap();
+        assert_eq!(job.name, "build");
+        assert_eq!(job.status, Some(RunStatus::InProgress));
+        assert_eq!(job.labels, vec!["self-hosted", "foo", "bar"]);
+        assert_eq!(job.steps[0].conclusion, Some(Conclusion::Success));
+        assert_eq!(job.steps[0].started_at, Some(Utc.with_ymd_and_hms(2020, 1, 20, 17, 42, 40).unwrap()));
+    }
+
+    #[test]
+    fn durations() {
+        let job = serde_json::from_str::<Job>(JOB).unwrap();
+        let now = Utc.with_ymd_and_hms(2020, 1, 20, 17, 45, 40).unwrap();
+        assert_eq!(job.steps[0].duration(now), Some(Duration::seconds(1)));
+        assert_eq!(job.steps[1].duration(now), Some(Duration::seconds(179)), "running, so far");
+        assert_eq!(job.duration(now), Some(Duration::minutes(3)));
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use serde_yaml::Value;
+
+/// An input of a `workflow_dispatch` trigger, in the order the workflow declares them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DispatchInput {
+    pub name: String,
+    pub description: String,
+    pub required: bool,
+    pub default: String,
+    pub kind: InputKind,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum InputKind {
+    String,
+    Number,
+    Boolean,
+    Choice(Vec<String>),
+    /// One of the repository's environments.
+    Environment,
+}
+
+impl DispatchInput {
+    /// Whether GitHub will take `value` for this input, numbers must parse unless left out of an optional input.
+    pub fn accepts(&self, value: &str) -> bool {
+        match self.kind {
+            InputKind::Number if value.is_empty() => !self.required,
+            InputKind::Number => value.trim().parse::<f64>().is_ok(),
+            _ => !self.required || !value.is_empty(),
+        }
+    }
+}
+
+/// Body of `POST /repos/{owner}/{repo}/actions/workflows/{id}/dispatches`.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Dispatch {
+    #[serde(rename = "ref")]
+    pub git_ref: String,
+    pub inputs: BTreeMap<String, String>,
+}
+
+/// Inputs of the `workflow_dispatch` trigger in a workflow file,
+/// `None` when the workflow cannot be dispatched manually.
+pub fn dispatch_inputs(yaml: &str) -> Result<Option<Vec<DispatchInput>>, String> {
+    let workflow = serde_yaml::from_str::<Value>(yaml).map_err(|e| format!("Invalid workflow: {e}"))?;
+
+    // YAML 1.1 parsers read `on` as `true`, which some workflows have been written around
+    let on = workflow.get("on").or_else(|| workflow.get(Value::Bool(true)));
+    let trigger = match on {
+        Some(Value::String(event)) => return Ok((event == "workflow_dispatch").then(Vec::new)),
+        Some(Value::Sequence(events)) => {
+            let dispatchable = events.iter().any(|event| event.as_str() == Some("workflow_dispatch"));
+            return Ok(dispatchable.then(Vec::new));
+        }
+        Some(Value::Mapping(events)) => match events.get("workflow_dispatch") {
+            Some(trigger) => trigger,
+            None => return Ok(None),
+        },
+        _ => return Ok(None),
+    };
+
+    let Some(inputs) = trigger.get("inputs").and_then(Value::as_mapping) else {
+        return Ok(Some(vec![]));
+    };
+
+    inputs.iter()
+        .map(|(name, input)| {
+            let name = name.as_str().ok_or("Input names must be strings")?.to_string();
+            let text = |key: &str| input.get(key).map(scalar).unwrap_or_default();
+            let kind = match input.get("type").and_then(Value::as_str).unwrap_or("string") {
+                "string" => InputKind::String,
+                "number" => InputKind::Number,
+                "boolean" => InputKind::Boolean,
+                "environment" => InputKind::Environment,
+                "choice" => InputKind::Choice(input.get("options")
+                    .and_then(Value::as_sequence)
+                    .map(|options| options.iter().map(scalar).collect())
+                    .unwrap_or_default()),
+                other => return Err(format!("Unknown type {other} of input {name}")),
+            };
+
+            // an unchecked box is false, not left out
+            let default = match (&kind, text("default")) {
+                (InputKind::Boolean, default) if default.is_empty() => false.to_string(),
+                (_, default) => default,
+            };
+
+            Ok(DispatchInput {
+                description: text("description"),
+                required: input.get("required").and_then(Value::as_bool).unwrap_or_default(),
+                default,
+                name,
+                kind,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()
+        .map(Some)
+}
+
+/// Defaults may be written as numbers or booleans, the API wants every input as a string.
+fn scalar(value: &Value) -> String {
+    match value {
+        Value::String(string) => string.clone(),
+        Value::Bool(bool) => bool.to_string(),
+        Value::Number(number) => number.to_string(),
+        _ => String::default(),
+    }
+}
+
+#[cfg(test)]
+mod dispatch_inputs {
+    use crate::workflow_dispatch::{dispatch_inputs, DispatchInput, InputKind};
+
+    #[test]
+    fn typed_inputs() {
+        let yaml = r#"
+name: Migrate
+on:
+  push:
+    branches: [main]
+  workflow_dispatch:
+    inputs:
+      cluster:
+        description: Where to run it
+        type: environment
+        required: true
+      dry-run:
+        type: boolean
+        default: true
+      batch:
+        type: number
+        default: 500
+      mode:
+        type: choice
+        options: [full, incremental]
+        default: incremental
+      note:
+        description: Free text
+      force:
+        type: boolean
+        required: true
+"#;
+        let inputs = dispatch_inputs(yaml).unwrap().unwrap();
+        let names = inputs.iter().map(|input| input.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["cluster", "dry-run", "batch", "mode", "note", "force"]);
+        assert_eq!(inputs[0].kind, InputKind::Environment);
+        assert!(inputs[0].required);
+        assert_eq!(inputs[1].default, "true");
+        assert_eq!(inputs[2].default, "500");
+        assert_eq!(inputs[3].kind, InputKind::Choice(vec!["full".to_string(), "incremental".to_string()]));
+        assert_eq!(inputs[4].kind, InputKind::String);
+        assert_eq!(inputs[5].default, "false");
+        assert!(inputs[5].accepts(&inputs[5].default));
+    }
+
+    #[test]
+    fn accepted_values() {
+        let input = |kind: InputKind, required: bool| DispatchInput { name: "batch".to_string(), description: String::new(), required, default: String::new(), kind };
+        assert!(input(InputKind::Number, true).accepts("500"));
+        assert!(input(InputKind::Number, true).accepts("0.5"));
+        assert!(!input(InputKind::Number, true).accepts("five hundred"));
+        assert!(!input(InputKind::Number, true).accepts(""));
+        assert!(input(InputKind::Number, false).accepts(""));
+        assert!(!input(InputKind::String, true).accepts(""));
+        assert!(input(InputKind::String, false).accepts(""));
+    }
+
+    #[test]
+    fn without_inputs() {
+        assert_eq!(dispatch_inputs("on: workflow_dispatch").unwrap(), Some(vec![]));
+        assert_eq!(dispatch_inputs("on: [push, workflow_dispatch]").unwrap(), Some(vec![]));
+        assert_eq!(dispatch_inputs("on:\n  workflow_dispatch:\n").unwrap(), Some(vec![]));
+    }
+
+    #[test]
+    fn not_dispatchable() {
+        assert_eq!(dispatch_inputs("on: [push, pull_request]").unwrap(), None);
+        assert_eq!(dispatch_inputs("on:\n  push:\n    branches: [main]\n").unwrap(), None);
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::BTreeMap;
+use std::io::{Cursor, Read};
+
+/// Marks the lines GitHub Actions reports as errors.
+pub const ERROR_MARKER: &str = "##[error]";
+
+/// Logs of a run, as unpacked from the archive at [crate::workflow::WorkflowRun::logs_url].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Logs {
+    pub jobs: Vec<JobLog>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JobLog {
+    pub name: String,
+    /// In the order they ran.
+    pub steps: Vec<StepLog>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StepLog {
+    pub number: usize,
+    pub name: String,
+    pub lines: Vec<String>,
+}
+
+impl StepLog {
+    /// Index of the first line GitHub marked as an error.
+    pub fn first_error(&self) -> Option<usize> {
+        self.lines.iter().position(|line| line.contains(ERROR_MARKER))
+    }
+}
+
+impl Logs {
+    /// The archive has a `<job>/<number>_<step>.txt` per step, and a `<number>_<job>.txt` with the whole job.
+    /// The whole job is only used for jobs without step files.
+    pub fn from_zip(bytes: &[u8]) -> Result<Logs, String> {
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).map_err(|e| format!("Not a log archive: {e}"))?;
+        let mut steps = BTreeMap::<String, Vec<StepLog>>::new();
+        let mut whole_jobs = Vec::new();
+
+        for index in 0..archive.len() {
+            let mut file = archive.by_index(index).map_err(|e| e.to_string())?;
+            if file.is_dir() {
+                continue;
+            }
+            let path = file.name().to_string();
+            let mut bytes = Vec::new();
+            file.read_to_end(&mut bytes).map_err(|e| format!("{path}: {e}"))?;
+            // one stray byte in a tool's output should not hide the rest of the logs
+            let text = String::from_utf8_lossy(&bytes);
+
+            match path.split_once('/') {
+                Some((job, file_name)) => steps.entry(job.to_string()).or_default().push(step(file_name, &text)),
+                None => whole_jobs.push(step(&path, &text)),
+            }
+        }
+
+        whole_jobs.sort_by_key(|whole_job| whole_job.number);
+        let mut jobs = whole_jobs.into_iter()
+            .map(|whole_job| match steps.remove(&whole_job.name) {
+                Some(steps) => JobLog { name: whole_job.name, steps: sorted(steps) },
+                None => JobLog { name: whole_job.name.clone(), steps: vec![whole_job] },
+            })
+            .collect::<Vec<_>>();
+        jobs.extend(steps.into_iter().map(|(name, steps)| JobLog { name, steps: sorted(steps) }));
+
+        Ok(Logs { jobs })
+    }
+}
+
+fn sorted(mut steps: Vec<StepLog>) -> Vec<StepLog> {
+    steps.sort_by_key(|step| step.number);
+    steps
+}
+
+/// `3_Run tests.txt` is step 3, "Run tests".
+fn step(file_name: &str, text: &str) -> StepLog {
+    let stem = file_name.strip_suffix(".txt").unwrap_or(file_name);
+    let numbered = stem.split_once('_').and_then(|(number, name)| Some((number.parse::<usize>().ok()?, name)));
+    let (number, name) = numbered.unwrap_or((0, stem));
+    StepLog {
+        number,
+        name: name.to_string(),
+        lines: text.trim_start_matches('\u{feff}').lines().map(str::to_string).collect(),
+    }
+}
+
+#[cfg(test)]
+mod archive {
+    use std::io::{Cursor, Write};
+
+    use zip::write::FileOptions;
+    use zip::{CompressionMethod, ZipWriter};
+
+    use crate::workflow_log::Logs;
+
+    fn zip(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        files.iter().for_each(|(name, text)| {
+            writer.start_file(*name, FileOptions::default().compression_method(CompressionMethod::Deflated)).unwrap();
+            writer.write_all(text.as_bytes()).unwrap();
+        });
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn steps_per_job() {
+        let bytes = zip(&[
+            ("0_test.txt", "everything"),
+            ("1_deploy.txt", "everything"),
+            ("test/1_Set up job.txt", "\u{feff}2023-02-01T10:00:00.0000000Z Runner"),
+            ("deploy/10_Notify.txt", "sent"),
+            ("deploy/2_Deploy to prod-gcp.txt", "starting\n2023-02-01T10:00:01.0000000Z ##[error]Process completed with exit code 1."),
+        ]);
+        let logs = Logs::from_zip(&bytes).unwrap();
+
+        assert_eq!(logs.jobs.iter().map(|job| job.name.as_str()).collect::<Vec<_>>(), vec!["test", "deploy"]);
+        let deploy = &logs.jobs[1];
+        assert_eq!(deploy.steps.iter().map(|step| (step.number, step.name.as_str())).collect::<Vec<_>>(), vec![(2, "Deploy to prod-gcp"), (10, "Notify")]);
+        assert_eq!(deploy.steps[0].first_error(), Some(1));
+        assert_eq!(logs.jobs[0].steps[0].lines, vec!["2023-02-01T10:00:00.0000000Z Runner"]);
+    }
+
+    #[test]
+    fn whole_job_without_steps() {
+        let logs = Logs::from_zip(&zip(&[("0_build.txt", "one\ntwo")])).unwrap();
+        assert_eq!(logs.jobs[0].name, "build");
+        assert_eq!(logs.jobs[0].steps[0].lines, vec!["one", "two"]);
+        assert_eq!(logs.jobs[0].steps[0].first_error(), None);
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer.start_file("build/1_Build.txt", FileOptions::default()).unwrap();
+        writer.write_all(b"caf\xe9\n##[error]failed").unwrap();
+        let bytes = writer.finish().unwrap().into_inner();
+
+        let logs = Logs::from_zip(&bytes).unwrap();
+        assert_eq!(logs.jobs[0].steps[0].lines, vec!["caf\u{fffd}", "##[error]failed"]);
+    }
+
+    #[test]
+    fn not_a_zip() {
+        assert!(Logs::from_zip(b"{\"message\":\"Not Found\"}").unwrap_err().starts_with("Not a log archive"));
+    }
+}
@@ -0,0 +1,171 @@
+use egui::text::{LayoutJob, TextFormat};
+use egui::{Color32, FontId};
+
+/// Colour and weight set by the SGR escape sequences before a piece of text.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Style {
+    /// `None` is the default text colour.
+    pub color: Option<Color32>,
+    pub bold: bool,
+}
+
+/// The line split where the style changes, with every escape sequence taken out.
+/// Each line starts unstyled, as GitHub resets the style at the end of every line anyway.
+pub fn spans(line: &str) -> Vec<(Style, String)> {
+    let mut spans = vec![];
+    let mut style = Style::default();
+    let mut text = String::new();
+    let mut rest = line;
+
+    while let Some(start) = rest.find('\u{1b}') {
+        text.push_str(&rest[..start]);
+        let sequence = &rest[start + 1..];
+        let Some(parameters) = sequence.strip_prefix('[') else {
+            rest = sequence;
+            continue;
+        };
+        // parameters run until the final byte, a letter or one of @[\]^_`{|}~
+        let end = parameters.find(|c: char| ('@'..='~').contains(&c)).unwrap_or(parameters.len());
+        if parameters[end..].starts_with('m') {
+            let next = select_graphic_rendition(style, &parameters[..end]);
+            if next != style && !text.is_empty() {
+                spans.push((style, std::mem::take(&mut text)));
+            }
+            style = next;
+        }
+        rest = parameters.get(end + 1..).unwrap_or_default();
+    }
+    text.push_str(rest);
+    if !text.is_empty() || spans.is_empty() {
+        spans.push((style, text));
+    }
+    spans
+}
+
+/// The line without escape sequences, for searching.
+pub fn strip(line: &str) -> String {
+    spans(line).into_iter().map(|(_, text)| text).collect()
+}
+
+/// The line laid out in `font`, with `color` wherever it sets none, on `background`.
+pub fn layout(line: &str, font: FontId, color: Color32, background: Color32) -> LayoutJob {
+    let mut job = LayoutJob::default();
+    spans(line).into_iter().for_each(|(style, text)| {
+        let color = style.color.unwrap_or(color);
+        let color = match style.bold {
+            true => brighter(color),
+            false => color,
+        };
+        job.append(&text, 0.0, TextFormat { font_id: font.clone(), color, background, ..TextFormat::default() });
+    });
+    job
+}
+
+fn select_graphic_rendition(mut style: Style, parameters: &str) -> Style {
+    let codes = parameters.split(';').map(|code| code.parse::<u8>().unwrap_or(0)).collect::<Vec<_>>();
+    let mut codes = codes.into_iter();
+    while let Some(code) = codes.next() {
+        match code {
+            0 => style = Style::default(),
+            1 => style.bold = true,
+            22 => style.bold = false,
+            30..=37 => style.color = Some(PALETTE[usize::from(code - 30)]),
+            90..=97 => style.color = Some(PALETTE[usize::from(code - 90 + 8)]),
+            39 => style.color = None,
+            38 => style.color = extended(&mut codes).or(style.color),
+            // backgrounds are left out, but their arguments must be skipped
+            48 => { extended(&mut codes); }
+            _ => {}
+        }
+    }
+    style
+}
+
+/// `5;n` from the 256 colour palette or `2;r;g;b`.
+fn extended(codes: &mut impl Iterator<Item = u8>) -> Option<Color32> {
+    match codes.next()? {
+        5 => Some(indexed(codes.next()?)),
+        2 => Some(Color32::from_rgb(codes.next()?, codes.next()?, codes.next()?)),
+        _ => None,
+    }
+}
+
+fn indexed(index: u8) -> Color32 {
+    match index {
+        0..=15 => PALETTE[usize::from(index)],
+        16..=231 => {
+            let level = |value: u8| match value {
+                0 => 0,
+                value => 55 + value * 40,
+            };
+            let index = index - 16;
+            Color32::from_rgb(level(index / 36), level(index / 6 % 6), level(index % 6))
+        }
+        grey => {
+            let level = 8 + (grey - 232) * 10;
+            Color32::from_rgb(level, level, level)
+        }
+    }
+}
+
+fn brighter(color: Color32) -> Color32 {
+    let lift = |channel: u8| channel.saturating_add(40);
+    Color32::from_rgb(lift(color.r()), lift(color.g()), lift(color.b()))
+}
+
+/// The 8 normal and 8 bright colours, readable on the dark theme.
+const PALETTE: [Color32; 16] = [
+    Color32::from_rgb(0x4d, 0x4d, 0x4d),
+    Color32::from_rgb(0xe0, 0x6c, 0x75),
+    Color32::from_rgb(0x98, 0xc3, 0x79),
+    Color32::from_rgb(0xe5, 0xc0, 0x7b),
+    Color32::from_rgb(0x61, 0xaf, 0xef),
+    Color32::from_rgb(0xc6, 0x78, 0xdd),
+    Color32::from_rgb(0x56, 0xb6, 0xc2),
+    Color32::from_rgb(0xdc, 0xdf, 0xe4),
+    Color32::from_rgb(0x7f, 0x84, 0x8e),
+    Color32::from_rgb(0xff, 0x7b, 0x86),
+    Color32::from_rgb(0xb5, 0xe8, 0x90),
+    Color32::from_rgb(0xff, 0xd8, 0x8f),
+    Color32::from_rgb(0x80, 0xc8, 0xff),
+    Color32::from_rgb(0xe0, 0x94, 0xf5),
+    Color32::from_rgb(0x70, 0xd6, 0xe2),
+    Color32::from_rgb(0xff, 0xff, 0xff),
+];
+
+#[cfg(test)]
+mod sgr {
+    use egui::Color32;
+
+    use crate::ansi::{spans, strip, Style, PALETTE};
+
+    #[test]
+    fn plain() {
+        assert_eq!(spans("cargo test"), vec![(Style::default(), "cargo test".to_string())]);
+        assert_eq!(spans(""), vec![(Style::default(), String::new())]);
+    }
+
+    #[test]
+    fn colours_and_reset() {
+        let red = Style { color: Some(PALETTE[1]), bold: false };
+        let bold_green = Style { color: Some(PALETTE[2]), bold: true };
+        assert_eq!(spans("\u{1b}[31merror\u{1b}[0m: \u{1b}[1;32mok\u{1b}[m"), vec![
+            (red, "error".to_string()),
+            (Style::default(), ": ".to_string()),
+            (bold_green, "ok".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn extended_colours() {
+        let spans = spans("\u{1b}[38;2;10;20;30mrgb\u{1b}[48;5;196;38;5;196mred");
+        assert_eq!(spans[0].0.color, Some(Color32::from_rgb(10, 20, 30)));
+        assert_eq!(spans[1].0.color, Some(Color32::from_rgb(255, 0, 0)));
+    }
+
+    #[test]
+    fn other_sequences_are_dropped() {
+        assert_eq!(strip("\u{1b}[2Kprogress\u{1b}[1A 100%"), "progress 100%");
+        assert_eq!(strip("broken \u{1b}[31"), "broken ");
+    }
+}
@@ -1,38 +1,164 @@
+use chrono::Utc;
 use eframe::Frame;
-use egui::{CentralPanel, Context, SelectableLabel, SidePanel, TextEdit, TopBottomPanel};
+use egui::{CentralPanel, Color32, Context, SelectableLabel, SidePanel, TextEdit, TopBottomPanel};
 
+use crate::auto_refresh::countdown;
 use crate::panel::{Panels, SelectedPanel};
+use crate::sign_in::SignIn;
+use crate::timestamp;
+use crate::token_check::TokenCheck;
+use crate::vault::{Secrets, Vault};
 
 impl Application {
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
-        if let Some(storage) = cc.storage {
-            return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
+        let mut app: Application = match cc.storage {
+            Some(storage) => eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default(),
+            None => Default::default(),
+        };
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(transport) = http::fixture::from_env(app.panels.client.activity()) {
+            app.panels.client = app.panels.client.with_transport(transport);
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        match http::app::from_env() {
+            Some(Ok(github_app)) => app.panels.client.set_app(Some(github_app)),
+            Some(Err(e)) => app.app_error = Some(e),
+            None => {}
         }
 
-        Default::default()
+        // responses arrive on other threads, so show them without waiting for input
+        let ctx = cc.egui_ctx.clone();
+        app.panels.client.on_response(move || ctx.request_repaint());
+
+        app
     }
 }
 
 impl eframe::App for Application {
     /// Called each time the UI needs repainting, which may be many times per second.
     /// Put your widgets into a `SidePanel`, `TopPanel`, `CentralPanel`, `Window` or `Area`.
-    fn update(&mut self, ctx: &Context, _frame: &mut Frame) {
+    fn update(&mut self, ctx: &Context, frame: &mut Frame) {
         let Self {
             token,
             token_visible,
             panels,
+            token_check,
+            sign_in,
+            vault,
+            #[cfg(not(target_arch = "wasm32"))]
+            app_error,
         } = self;
 
+        if vault.locked() {
+            let mut forgotten = false;
+            CentralPanel::default().show(ctx, |ui| {
+                if let Some(secrets) = vault.paint_locked(ui) {
+                    *token = secrets.token;
+                    sign_in.restore(secrets.session);
+                    panels.client.cache().replace(&secrets.cache);
+                }
+                ui.separator();
+                if ui.button("Forget token").on_hover_text("Wipe the stored token and start over").clicked() {
+                    vault.forget();
+                    forgotten = true;
+                }
+            });
+            if forgotten {
+                self.persist(frame);
+            }
+            return;
+        }
+
+        sign_in.update(&panels.client, token);
+        let mut persist = false;
+
+        // browsers stop painting hidden tabs, so this is not even called there while hidden
+        #[cfg(not(target_arch = "wasm32"))]
+        let hidden = frame.info().window_info.minimized;
+        #[cfg(target_arch = "wasm32")]
+        let hidden = false;
+        if !hidden {
+            panels.auto_refresh(token, Utc::now());
+        }
+        if panels.auto_refreshes() {
+            // keeps the countdown ticking and notices when a refresh is due
+            ctx.request_repaint_after(std::time::Duration::from_secs(1));
+        }
+
         TopBottomPanel::top("top_panel").show(ctx, |ui| {
             ui.horizontal_wrapped(|ui| {
-                ui.label("PAT").on_hover_text("Get your Personal Access Token from Github and select repo and workflow persmissions");
-                ui.add(TextEdit::singleline(token).password(!*token_visible));
+                ui.label("PAT").on_hover_text("Get your Personal Access Token from Github and select the repo and read:org scopes");
+                let token_field = ui.add(TextEdit::singleline(token).password(!*token_visible));
                 if ui.add(SelectableLabel::new(*token_visible, "👁")).on_hover_text("Show/hide token").clicked() {
                     *token_visible = !*token_visible;
                 };
-           
-                let client = panels.selected.get_client();
-                ui.label(format!("{} API requests remaining until {}", client.get_rate_limit(), client.get_rate_reset()));
+                if vault.encrypted() && ui.button("🔒").on_hover_text("Lock the token until the passphrase is entered").clicked() {
+                    vault.lock();
+                    token.clear();
+                    sign_in.forget();
+                }
+                if ui.button("Forget token").on_hover_text("Wipe the token from memory and storage").clicked() {
+                    vault.forget();
+                    token.clear();
+                    sign_in.forget();
+                    persist = true;
+                }
+
+                // check once the token is entered, not on every keystroke
+                if !token_field.has_focus() && token_check.checked() != token.as_str() {
+                    if !token_check.checked().is_empty() {
+                        panels.client.clear_cache();
+                    }
+                    token_check.check(&mut panels.client, token);
+                }
+
+                match panels.rate_limit() {
+                    Some(rate_limit) => ui.label(format!(
+                        "{} of {} API requests remaining until {}",
+                        rate_limit.remaining,
+                        rate_limit.limit,
+                        timestamp::clock(rate_limit.reset_at(), "%H:%M"),
+                    )),
+                    None => ui.label("API requests remaining is unknown until the first request"),
+                };
+
+                if let Some(rate_limit) = panels.client.graphql_rate_limit() {
+                    ui.label(format!(
+                        "{} of {} GraphQL points remaining, last query cost {}",
+                        rate_limit.remaining,
+                        rate_limit.limit,
+                        rate_limit.cost.unwrap_or_default(),
+                    ));
+                }
+
+                let client = &panels.client;
+                if client.in_flight() + client.queued() > 0 {
+                    ui.label(format!("{} requests in flight, {} queued", client.in_flight(), client.queued()));
+                }
+
+                if let Some((name, next)) = panels.next_refresh() {
+                    match hidden {
+                        true => ui.label(format!("Auto-refresh of {name} paused while hidden")),
+                        false => ui.label(format!("{name} refresh in {}", countdown(next, Utc::now()))),
+                    };
+                }
+            });
+
+            ui.horizontal_wrapped(|ui| {
+                #[cfg(not(target_arch = "wasm32"))]
+                if token.trim().is_empty() && panels.client.has_app() {
+                    ui.label("Authenticated as GitHub App installation");
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(e) = app_error {
+                    ui.colored_label(Color32::LIGHT_RED, format!("Not authenticating as GitHub App: {e}"));
+                }
+                // github.com does not allow the device flow from a browser
+                #[cfg(not(target_arch = "wasm32"))]
+                sign_in.paint(ui, &panels.client);
+                token_check.paint(ui)
             });
         });
 
@@ -41,47 +167,94 @@ impl eframe::App for Application {
                 ui.heading("GitHub Status");
                 ui.group(|ui| {
                     ui.separator();
-                    let client = panels.selected.get_client();
 
                     if ui.button("  Repositories  ").clicked() {
-                        panels.selected = SelectedPanel::Repositories(client.clone())
+                        panels.selected = SelectedPanel::Repositories
                     }
                     ui.separator();
                     if ui.button("  Pull Requests ").clicked() {
-                        panels.selected = SelectedPanel::PullRequests(client.clone())
+                        panels.selected = SelectedPanel::PullRequests
                     }
                     ui.separator();
                     if ui.button("   Deployments  ").clicked() {
-                        panels.selected = SelectedPanel::Deployments(client.clone())
+                        panels.selected = SelectedPanel::Deployments
                     }
                     ui.separator();
                     if ui.button("    Workflows   ").clicked() {
-                        panels.selected = SelectedPanel::WorkflowRuns(client.clone())
+                        panels.selected = SelectedPanel::WorkflowRuns
+                    }
+                    ui.separator();
+                    if ui.button("    Activity    ").clicked() {
+                        panels.selected = SelectedPanel::Activity
                     }
                     ui.separator();
                 });
+
+                ui.collapsing("Settings", |ui| {
+                    let settings = panels.client.settings_mut();
+                    ui.label("Organisation");
+                    ui.text_edit_singleline(&mut settings.org);
+                    ui.label("API URL").on_hover_text("https://HOST/api/v3 for GitHub Enterprise Server");
+                    ui.text_edit_singleline(&mut settings.api_url);
+                    #[cfg(not(target_arch = "wasm32"))]
+                    {
+                        ui.label("OAuth client id").on_hover_text("Of an OAuth App with device flow enabled, for Sign in with GitHub");
+                        ui.text_edit_singleline(&mut settings.oauth_client_id);
+                    }
+                    ui.label("Token storage");
+                    let secrets = Secrets { token: token.clone(), session: sign_in.session(), cache: panels.client.cache() };
+                    persist |= vault.paint_settings(ui, &secrets);
+                });
             });
         });
 
         CentralPanel::default().show(ctx, |ui| {
             match panels.selected {
-                SelectedPanel::Repositories(_) => panels.paint_repositories(ui, token),
-                SelectedPanel::PullRequests(_) => panels.paint_pull_requests(ui, token),
-                SelectedPanel::Deployments(_) => panels.paint_deployments(ui, token),
-                SelectedPanel::WorkflowRuns(_) => panels.paint_workflows(ui, token),
+                SelectedPanel::Repositories => panels.paint_repositories(ui, token),
+                SelectedPanel::PullRequests => panels.paint_pull_requests(ui, token),
+                SelectedPanel::Deployments => panels.paint_deployments(ui, token),
+                SelectedPanel::WorkflowRuns => panels.paint_workflows(ui, token),
+                SelectedPanel::Activity => panels.paint_activity(ui, token),
             }
         });
+
+        if persist {
+            self.persist(frame);
+        }
     }
 
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        self.vault.store(&Secrets { token: self.token.clone(), session: self.sign_in.session(), cache: self.panels.client.cache() });
         eframe::set_value(storage, eframe::APP_KEY, self);
     }
 }
 
+impl Application {
+    /// Writes storage right away instead of at the next autosave, e.g. so a forgotten token is gone at once.
+    fn persist(&mut self, frame: &mut Frame) {
+        if let Some(storage) = frame.storage_mut() {
+            eframe::App::save(self, storage);
+            storage.flush();
+        }
+    }
+}
+
 #[derive(serde::Deserialize, serde::Serialize, Default)]
 pub struct Application {
+    /// Only stored encrypted, through [Vault].
+    #[serde(skip)]
     token: String,
     token_visible: bool,
     panels: Panels,
+    #[serde(skip)]
+    token_check: TokenCheck,
+    #[serde(skip)]
+    sign_in: SignIn,
+    #[serde(default)]
+    vault: Vault,
+    /// Why the App configured in the environment could not be used.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    app_error: Option<String>,
 }
 
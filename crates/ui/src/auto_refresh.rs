@@ -0,0 +1,159 @@
+use chrono::{DateTime, Duration, Utc};
+use egui::{DragValue, Ui};
+use serde::{Deserialize, Serialize};
+
+use http::rate_limit::RateLimit;
+
+use crate::panel::Panel;
+
+/// Below this share of the quota left, automatic refreshes wait for the reset and leave the rest for clicks.
+const RESERVED_PERCENT: usize = 10;
+/// Below this share of the quota left, automatic refreshes are spread out.
+const BACK_OFF_PERCENT: usize = 25;
+const BACK_OFF_FACTOR: i32 = 4;
+
+/// Refreshing a panel on a timer, with an interval of its own.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct AutoRefresh {
+    enabled: bool,
+    interval_minutes: u32,
+    #[serde(skip)]
+    next: Option<DateTime<Utc>>,
+}
+
+impl Default for AutoRefresh {
+    fn default() -> Self {
+        AutoRefresh { enabled: false, interval_minutes: 5, next: None }
+    }
+}
+
+/// A panel that can be refreshed without anyone clicking.
+pub trait AutoRefreshing: Panel {
+    fn auto_refresh(&mut self) -> &mut AutoRefresh;
+    /// Fetches what the panel shows, as its refresh button does.
+    fn refresh_now(&mut self, token: &str);
+}
+
+impl AutoRefresh {
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// When the next refresh is due, `None` while disabled.
+    pub fn next(&self) -> Option<DateTime<Utc>> {
+        match self.enabled {
+            true => self.next,
+            false => None,
+        }
+    }
+
+    /// True when a refresh should start now, then plans the next one.
+    /// The wait is stretched when little of any quota is left, and lasts until the reset when it is nearly used up.
+    pub fn due(&mut self, now: DateTime<Utc>, rate_limits: &[RateLimit]) -> bool {
+        if !self.enabled {
+            self.next = None;
+            return false;
+        }
+        if matches!(self.next, Some(next) if next > now) {
+            return false;
+        }
+
+        let reserved = rate_limits.iter()
+            .filter(|rate_limit| below(rate_limit, RESERVED_PERCENT) && rate_limit.reset_at() > now)
+            .map(RateLimit::reset_at)
+            .max();
+        if let Some(reset) = reserved {
+            self.next = Some(reset);
+            return false;
+        }
+
+        let interval = Duration::minutes(self.interval_minutes.into());
+        let interval = match rate_limits.iter().any(|rate_limit| below(rate_limit, BACK_OFF_PERCENT)) {
+            true => interval * BACK_OFF_FACTOR,
+            false => interval,
+        };
+        self.next = Some(now + interval);
+        true
+    }
+
+    /// Checkbox and interval, next to the panel's refresh button.
+    pub fn paint(&mut self, ui: &mut Ui) {
+        if ui.checkbox(&mut self.enabled, "Auto-refresh every").changed() {
+            self.next = None;
+        }
+        let interval = ui.add(DragValue::new(&mut self.interval_minutes).clamp_range(1..=240).suffix(" min"));
+        if interval.changed() {
+            self.next = None;
+        }
+    }
+}
+
+fn below(rate_limit: &RateLimit, percent: usize) -> bool {
+    rate_limit.remaining * 100 < rate_limit.limit * percent
+}
+
+/// Minutes and seconds until `next`, for the status bar.
+pub fn countdown(next: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let seconds = (next - now).num_seconds().max(0);
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod due {
+    use chrono::{Duration, TimeZone, Utc};
+
+    use http::rate_limit::RateLimit;
+
+    use crate::auto_refresh::{countdown, AutoRefresh};
+
+    fn enabled(interval_minutes: u32) -> AutoRefresh {
+        AutoRefresh { enabled: true, interval_minutes, next: None }
+    }
+
+    fn rate_limit(remaining: usize, reset: i64) -> RateLimit {
+        RateLimit { limit: 5000, remaining, reset, resource: "core".to_string(), ..RateLimit::default() }
+    }
+
+    #[test]
+    fn at_once_then_every_interval() {
+        let now = Utc.with_ymd_and_hms(2023, 3, 1, 12, 0, 0).unwrap();
+        let mut auto_refresh = enabled(5);
+        assert!(auto_refresh.due(now, &[]));
+        assert!(!auto_refresh.due(now + Duration::minutes(4), &[]));
+        assert!(auto_refresh.due(now + Duration::minutes(5), &[]));
+        assert_eq!(auto_refresh.next(), Some(now + Duration::minutes(10)));
+    }
+
+    #[test]
+    fn disabled() {
+        let mut auto_refresh = AutoRefresh::default();
+        assert!(!auto_refresh.due(Utc::now(), &[]));
+        assert_eq!(auto_refresh.next(), None);
+    }
+
+    #[test]
+    fn backs_off_when_quota_is_low() {
+        let now = Utc.with_ymd_and_hms(2023, 3, 1, 12, 0, 0).unwrap();
+        let mut auto_refresh = enabled(5);
+        assert!(auto_refresh.due(now, &[rate_limit(1000, now.timestamp() + 3600)]));
+        assert_eq!(auto_refresh.next(), Some(now + Duration::minutes(20)));
+    }
+
+    #[test]
+    fn waits_for_reset_when_quota_is_reserved() {
+        let now = Utc.with_ymd_and_hms(2023, 3, 1, 12, 0, 0).unwrap();
+        let reset = now + Duration::minutes(42);
+        let mut auto_refresh = enabled(5);
+        assert!(!auto_refresh.due(now, &[rate_limit(4000, 0), rate_limit(100, reset.timestamp())]));
+        assert_eq!(auto_refresh.next(), Some(reset));
+
+        assert!(auto_refresh.due(reset, &[rate_limit(5000, reset.timestamp() + 3600)]));
+    }
+
+    #[test]
+    fn minutes_and_seconds() {
+        let now = Utc::now();
+        assert_eq!(countdown(now + Duration::seconds(272), now), "4:32");
+        assert_eq!(countdown(now - Duration::seconds(3), now), "0:00");
+    }
+}
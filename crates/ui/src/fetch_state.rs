@@ -0,0 +1,114 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use egui::{Color32, Ui};
+
+use crate::timestamp;
+
+/// Where the latest fetch of something stands.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum FetchState {
+    #[default]
+    Idle,
+    Loading,
+    Loaded(DateTime<Utc>),
+    Failed(String),
+}
+
+/// Key of a failed GraphQL query, whose repositories were fetched from the REST API instead.
+pub const GRAPHQL: &str = "GraphQL";
+
+/// Fetch state per repository, or whatever else a panel fetches on its own,
+/// shared with the callbacks that fill in the data.
+#[derive(Clone, Default)]
+pub struct Fetches(Arc<Mutex<BTreeMap<String, FetchState>>>);
+
+impl Fetches {
+    pub fn clear(&self) {
+        self.0.lock().unwrap().clear();
+    }
+
+    pub fn start(&self, key: &str) {
+        self.set(key, FetchState::Loading);
+    }
+
+    pub fn loaded(&self, key: &str) {
+        self.set(key, FetchState::Loaded(Utc::now()));
+    }
+
+    pub fn failed(&self, key: &str, reason: impl Display) {
+        self.set(key, FetchState::Failed(reason.to_string()));
+    }
+
+    pub fn get(&self, key: &str) -> FetchState {
+        self.0.lock().unwrap().get(key).cloned().unwrap_or_default()
+    }
+
+    /// Marks `key` as loading, and returns the callback that records how the fetch went
+    /// and hands a successful result to `on_loaded`.
+    pub fn track<T, E: Display>(
+        &self,
+        key: &str,
+        on_loaded: impl FnOnce(T) + Send + 'static,
+    ) -> impl FnOnce(Result<T, E>) + Send + 'static {
+        self.start(key);
+        let fetches = self.clone();
+        let key = key.to_string();
+        move |result| match result {
+            Ok(value) => {
+                on_loaded(value);
+                fetches.loaded(&key);
+            }
+            Err(e) => fetches.failed(&key, e),
+        }
+    }
+
+    /// Each failed key with the reason.
+    pub fn failures(&self) -> Vec<(String, String)> {
+        self.0.lock().unwrap().iter()
+            .filter_map(|(key, state)| match state {
+                FetchState::Failed(reason) => Some((key.clone(), reason.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Spinner while anything loads, when the panel was last refreshed and how many fetches failed.
+    pub fn paint_summary(&self, ui: &mut Ui) {
+        let states = self.0.lock().unwrap().values().cloned().collect::<Vec<_>>();
+        let loading = states.iter().filter(|state| **state == FetchState::Loading).count();
+        if loading > 0 {
+            ui.spinner();
+            ui.label(format!("Loading {loading}"));
+        }
+
+        let last_loaded = states.iter().filter_map(|state| match state {
+            FetchState::Loaded(at) => Some(*at),
+            _ => None,
+        }).max();
+        if let Some(at) = last_loaded {
+            ui.label(format!("Last refreshed {}", timestamp::clock(at, "%H:%M:%S")));
+        }
+
+        let failures = self.failures();
+        if !failures.is_empty() {
+            let reasons = failures.iter().map(|(key, reason)| format!("{key}: {reason}")).collect::<Vec<_>>();
+            ui.colored_label(Color32::LIGHT_RED, format!("{} failed", failures.len())).on_hover_text(reasons.join("\n"));
+        }
+    }
+
+    /// Spinner or error badge for one row.
+    pub fn paint_badge(&self, ui: &mut Ui, key: &str) {
+        match self.get(key) {
+            FetchState::Loading => { ui.spinner(); }
+            FetchState::Failed(reason) => { ui.colored_label(Color32::LIGHT_RED, "⚠").on_hover_text(reason); }
+            FetchState::Idle | FetchState::Loaded(_) => {}
+        }
+    }
+
+    fn set(&self, key: &str, state: FetchState) {
+        self.0.lock().unwrap().insert(key.to_string(), state);
+    }
+}
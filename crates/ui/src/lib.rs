@@ -1,12 +1,23 @@
 use egui::{ScrollArea, Ui};
 use egui_extras::{Column, Size, StripBuilder, TableBuilder};
 
+pub mod ansi;
 pub mod app;
+pub mod auto_refresh;
+pub mod fetch_state;
+pub mod log_viewer;
 pub mod panel;
+pub mod panel_activity;
 pub mod panel_deployment;
 pub mod panel_pull_request;
 pub mod panel_repository;
 pub mod panel_workflows;
+pub mod sign_in;
+pub mod timestamp;
+pub mod token_check;
+pub mod vault;
+pub mod workflow_dispatch;
+pub mod workflow_jobs;
 
 trait Table {
     fn create<'b>(ui: &'b mut Ui, columns: Vec<&'b str>) -> egui_extras::Table<'b>;
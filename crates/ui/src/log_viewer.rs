@@ -0,0 +1,260 @@
+use std::sync::{Arc, Mutex};
+
+use egui::{Button, Color32, ComboBox, Label, ScrollArea, TextEdit, TextStyle, Ui, Window};
+use regex::Regex;
+
+use http::error::GitHubError;
+use http::github;
+use model::workflow::WorkflowRun;
+use model::workflow_log::{Logs, StepLog, ERROR_MARKER};
+
+use crate::ansi;
+
+type Archive = Result<Logs, String>;
+
+const MATCH_BACKGROUND: Color32 = Color32::from_rgb(0x4a, 0x40, 0x10);
+const CURRENT_MATCH_BACKGROUND: Color32 = Color32::from_rgb(0x80, 0x6a, 0x10);
+
+/// Log text of a run per job and step, downloaded as one archive.
+pub struct LogViewer {
+    title: String,
+    logs: Arc<Mutex<Option<Archive>>>,
+    job: usize,
+    step: usize,
+    /// Regular expression typed by the user.
+    search: String,
+    /// Lines of the selected step matching [LogViewer::search], and for which job, step and search they were found.
+    matches: Result<Vec<usize>, String>,
+    searched: Option<(usize, usize, String)>,
+    current: usize,
+    scroll_to: Option<usize>,
+    /// Whether the step with the first error has been selected since the logs arrived.
+    selected_failure: bool,
+}
+
+impl LogViewer {
+    pub fn open(client: &mut github::Client, token: &str, repo_name: &str, run: &WorkflowRun) -> Self {
+        let viewer = LogViewer {
+            title: format!("Logs of {repo_name} {} #{}", run.name.clone().unwrap_or_default(), run.id),
+            logs: Arc::default(),
+            job: 0,
+            step: 0,
+            search: String::new(),
+            matches: Ok(vec![]),
+            searched: None,
+            current: 0,
+            scroll_to: None,
+            selected_failure: false,
+        };
+
+        // GitHub answers with a redirect to the archive, which is followed
+        let logs = viewer.logs.clone();
+        client.get(token, &run.logs_url, move |response| {
+            let archive = response.map_err(|e| e.to_string()).and_then(|response| match response.ok {
+                true => Logs::from_zip(&response.bytes),
+                false => Err(GitHubError::from_response(&response).to_string()),
+            });
+            *logs.lock().unwrap() = Some(archive);
+        });
+
+        viewer
+    }
+
+    /// Paints the logs as a window, returns false once the user has closed it.
+    pub fn paint(&mut self, ui: &mut Ui) -> bool {
+        let mut open = true;
+        // logs can be large, so they are painted under the lock rather than cloned every frame
+        let logs = self.logs.clone();
+        let logs = logs.lock().unwrap();
+
+        Window::new(self.title.clone()).id(egui::Id::new("log_viewer")).open(&mut open).default_size([900.0, 600.0]).show(ui.ctx(), |ui| {
+            match &*logs {
+                None => { ui.spinner(); }
+                Some(Err(e)) => { ui.colored_label(Color32::LIGHT_RED, e); }
+                Some(Ok(logs)) if logs.jobs.is_empty() => { ui.label("The run has no logs"); }
+                Some(Ok(logs)) => self.paint_logs(ui, logs),
+            }
+        });
+
+        open
+    }
+
+    fn paint_logs(&mut self, ui: &mut Ui, logs: &Logs) {
+        if !self.selected_failure {
+            self.selected_failure = true;
+            if let Some((job, step, line)) = first_error(logs) {
+                (self.job, self.step, self.scroll_to) = (job, step, Some(line));
+            }
+        }
+
+        ui.horizontal_wrapped(|ui| {
+            ComboBox::from_id_source("log_job").selected_text(&logs.jobs[self.job].name).show_ui(ui, |ui| {
+                logs.jobs.iter().enumerate().for_each(|(index, job)| {
+                    let failed = job.steps.iter().any(|step| step.first_error().is_some());
+                    if ui.selectable_label(self.job == index, label(&job.name, failed)).clicked() {
+                        (self.job, self.step) = (index, 0);
+                    }
+                });
+            });
+
+            let steps = &logs.jobs[self.job].steps;
+            self.step = self.step.min(steps.len().saturating_sub(1));
+            ComboBox::from_id_source("log_step").selected_text(step_name(&steps[self.step])).show_ui(ui, |ui| {
+                steps.iter().enumerate().for_each(|(index, step)| {
+                    if ui.selectable_label(self.step == index, label(&step_name(step), step.first_error().is_some())).clicked() {
+                        self.step = index;
+                    }
+                });
+            });
+        });
+
+        let step = &logs.jobs[self.job].steps[self.step];
+        self.paint_search(ui, step);
+        ui.separator();
+        self.paint_lines(ui, step);
+    }
+
+    fn paint_search(&mut self, ui: &mut Ui, step: &StepLog) {
+        ui.horizontal_wrapped(|ui| {
+            ui.add(TextEdit::singleline(&mut self.search).hint_text("Search, regular expression").desired_width(250.0));
+
+            let searched = Some((self.job, self.step, self.search.clone()));
+            if self.searched != searched {
+                self.searched = searched;
+                self.matches = matching_lines(&step.lines, &self.search);
+                self.current = 0;
+                if let Ok(matches) = &self.matches {
+                    self.scroll_to = matches.first().copied().or(self.scroll_to);
+                }
+            }
+
+            match &self.matches {
+                Err(e) => { ui.colored_label(Color32::LIGHT_RED, e); }
+                Ok(_) if self.search.is_empty() => {}
+                Ok(matches) => {
+                    match matches.is_empty() {
+                        true => ui.label("No matches"),
+                        false => ui.label(format!("{} of {}", self.current + 1, matches.len())),
+                    };
+                    if ui.add_enabled(!matches.is_empty(), Button::new("⏶")).on_hover_text("Previous match").clicked() {
+                        self.current = (self.current + matches.len() - 1) % matches.len();
+                        self.scroll_to = Some(matches[self.current]);
+                    }
+                    if ui.add_enabled(!matches.is_empty(), Button::new("⏷")).on_hover_text("Next match").clicked() {
+                        self.current = (self.current + 1) % matches.len();
+                        self.scroll_to = Some(matches[self.current]);
+                    }
+                }
+            }
+
+            let error = step.first_error();
+            if ui.add_enabled(error.is_some(), Button::new("Jump to first error")).clicked() {
+                self.scroll_to = error;
+            }
+        });
+    }
+
+    /// Only the rows in view are laid out, so long logs scroll smoothly.
+    fn paint_lines(&mut self, ui: &mut Ui, step: &StepLog) {
+        let font = TextStyle::Monospace.resolve(ui.style());
+        let row_height = ui.text_style_height(&TextStyle::Monospace);
+        let text_color = ui.visuals().text_color();
+        let matches = self.matches.clone().unwrap_or_default();
+        let current = matches.get(self.current).copied();
+
+        let mut scroll_area = ScrollArea::both().auto_shrink([false, false]);
+        if let Some(line) = self.scroll_to.take() {
+            // a few lines of context above
+            let row = line.saturating_sub(3) as f32;
+            scroll_area = scroll_area.vertical_scroll_offset(row * (row_height + ui.spacing().item_spacing.y));
+        }
+
+        scroll_area.show_rows(ui, row_height, step.lines.len(), |ui, rows| {
+            rows.for_each(|index| {
+                let line = &step.lines[index];
+                let color = match line.contains(ERROR_MARKER) {
+                    true => Color32::LIGHT_RED,
+                    false if line.contains("##[warning]") => Color32::YELLOW,
+                    false => text_color,
+                };
+                let background = match (current == Some(index), !self.search.is_empty() && matches.binary_search(&index).is_ok()) {
+                    (true, _) => CURRENT_MATCH_BACKGROUND,
+                    (false, true) => MATCH_BACKGROUND,
+                    (false, false) => Color32::TRANSPARENT,
+                };
+                ui.add(Label::new(ansi::layout(line, font.clone(), color, background)).wrap(false));
+            });
+        });
+    }
+}
+
+fn label(name: &str, failed: bool) -> String {
+    match failed {
+        true => format!("✖ {name}"),
+        false => name.to_string(),
+    }
+}
+
+fn step_name(step: &StepLog) -> String {
+    format!("{}. {}", step.number, step.name)
+}
+
+/// Job, step and line of the first `##[error]` in the run.
+fn first_error(logs: &Logs) -> Option<(usize, usize, usize)> {
+    logs.jobs.iter().enumerate().find_map(|(job_index, job)| {
+        job.steps.iter().enumerate().find_map(|(step_index, step)| step.first_error().map(|line| (job_index, step_index, line)))
+    })
+}
+
+/// Lines where `pattern` matches the text without escape sequences, none for an empty pattern.
+fn matching_lines(lines: &[String], pattern: &str) -> Result<Vec<usize>, String> {
+    if pattern.is_empty() {
+        return Ok(vec![]);
+    }
+    let regex = Regex::new(pattern).map_err(|e| e.to_string())?;
+    Ok(lines.iter().enumerate()
+        .filter(|(_, line)| regex.is_match(&ansi::strip(line)))
+        .map(|(index, _)| index)
+        .collect())
+}
+
+#[cfg(test)]
+mod search {
+    use std::sync::Arc;
+
+    use http::github::Client;
+    use http::transport::FakeTransport;
+    use model::workflow::WorkflowRun;
+    use model::workflow_log::{JobLog, Logs, StepLog};
+
+    use crate::log_viewer::{first_error, matching_lines, LogViewer};
+
+    fn step(number: usize, lines: &[&str]) -> StepLog {
+        StepLog { number, name: format!("step {number}"), lines: lines.iter().map(|line| line.to_string()).collect() }
+    }
+
+    #[test]
+    fn regex_ignores_colours() {
+        let lines = vec!["test a ... \u{1b}[32mok\u{1b}[0m".to_string(), "test b ... FAILED".to_string(), "test c ... ok".to_string()];
+        assert_eq!(matching_lines(&lines, r"\.\.\. ok$").unwrap(), vec![0, 2]);
+        assert_eq!(matching_lines(&lines, "").unwrap(), Vec::<usize>::new());
+        assert!(matching_lines(&lines, "(unclosed").is_err());
+    }
+
+    #[test]
+    fn first_error_across_jobs() {
+        let logs = Logs { jobs: vec![
+            JobLog { name: "test".to_string(), steps: vec![step(1, &["fine"])] },
+            JobLog { name: "deploy".to_string(), steps: vec![step(1, &["fine"]), step(2, &["starting", "##[error]Process completed with exit code 1."])] },
+        ] };
+        assert_eq!(first_error(&logs), Some((1, 1, 1)));
+    }
+
+    #[test]
+    fn failed_download() {
+        let transport = FakeTransport::default().respond("*/logs", 410, r#"{"message":"Gone"}"#);
+        let run = WorkflowRun { id: 14, logs_url: "https://api.github.com/repos/navikt/api/actions/runs/14/logs".to_string(), ..WorkflowRun::default() };
+        let viewer = LogViewer::open(&mut Client::default().with_transport(Arc::new(transport)), "token", "api", &run);
+        assert_eq!(viewer.logs.lock().unwrap().clone().unwrap().unwrap_err(), "410: Gone");
+    }
+}
@@ -1,75 +1,116 @@
+use chrono::{DateTime, Utc};
 use egui::Ui;
 use http::github::Client;
+use http::rate_limit::RateLimit;
+use http::scheduler::Priority;
 use serde::{Deserialize, Serialize};
 
 use model::repository::Repository;
 
+use crate::auto_refresh::AutoRefreshing;
+use crate::panel_activity::ActivityPanel;
 use crate::panel_deployment::DeploymentPanel;
 use crate::panel_pull_request::PullRequestsPanel;
 use crate::panel_repository::RepositoriesPanel;
 use crate::panel_workflows::WorkflowPanel;
 
-#[derive(Deserialize, Serialize, Clone)]
-pub enum SelectedPanel {
-    Repositories(Client),
-    PullRequests(Client),
-    Deployments(Client),
-    WorkflowRuns(Client),
-}
-
-impl Default for SelectedPanel {
-    fn default() -> Self {
-        SelectedPanel::Repositories(Client::default())
-    }
-}
+/// How old a response one panel fetched may be and still be good enough for another.
+pub const SHARED_MAX_AGE_SECONDS: i64 = 60;
 
-impl SelectedPanel {
-    pub fn get_client(&self) -> Client {
-        match self {
-            SelectedPanel::Repositories(client) => client.clone(),
-            SelectedPanel::PullRequests(client) => client.clone(),
-            SelectedPanel::Deployments(client) => client.clone(),
-            SelectedPanel::WorkflowRuns(client) => client.clone(),
-        }
-    }
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SelectedPanel {
+    #[default]
+    Repositories,
+    PullRequests,
+    Deployments,
+    WorkflowRuns,
+    Activity,
 }
 
 #[derive(Deserialize, Serialize, Default)]
 pub struct Panels {
     pub selected: SelectedPanel,
+    /// The one client every panel gets a clone of, so they share rate limit state.
+    pub client: Client,
     pub repositories: RepositoriesPanel,
     pub pull_requests: PullRequestsPanel,
     pub deployment: DeploymentPanel,
     pub workflow: WorkflowPanel,
+    #[serde(default)]
+    pub activity: ActivityPanel,
 }
 
 impl Panels {
-    pub fn rate_limit(&self) -> usize {
-        self.selected.get_client().get_rate_limit()
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        self.client.rate_limit()
+    }
+
+    /// Starts the refreshes that are due, in the background lane so clicks go first.
+    /// Waits while earlier background requests are still queued.
+    pub fn auto_refresh(&mut self, token: &str, now: DateTime<Utc>) {
+        if self.client.queued() > 0 {
+            return;
+        }
+
+        let rate_limits = [self.client.rate_limit(), self.client.graphql_rate_limit()].into_iter().flatten().collect::<Vec<_>>();
+        let repositories = self.repositories.repositories();
+        let client = self.client.with_priority(Priority::Background);
+        self.auto_refreshing().into_iter().for_each(|(_, panel)| {
+            if panel.auto_refresh().due(now, &rate_limits) {
+                panel.set_repositories(repositories.clone());
+                panel.set_client(client.clone());
+                panel.refresh_now(token);
+            }
+        });
+    }
+
+    /// The panel that is refreshed automatically first, and when.
+    pub fn next_refresh(&mut self) -> Option<(&'static str, DateTime<Utc>)> {
+        self.auto_refreshing().into_iter()
+            .filter_map(|(name, panel)| panel.auto_refresh().next().map(|next| (name, next)))
+            .min_by_key(|(_, next)| *next)
     }
 
-    pub fn rate_limit_reset(&self) -> u64 {
-        self.selected.get_client().get_rate_reset()
+    /// Whether any panel refreshes itself.
+    pub fn auto_refreshes(&mut self) -> bool {
+        self.auto_refreshing().into_iter().any(|(_, panel)| panel.auto_refresh().enabled())
+    }
+
+    fn auto_refreshing(&mut self) -> [(&'static str, &mut dyn AutoRefreshing); 3] {
+        [
+            ("Pull requests", &mut self.pull_requests),
+            ("Deployments", &mut self.deployment),
+            ("Workflows", &mut self.workflow),
+        ]
     }
 
     pub fn paint_repositories(&mut self, ui: &mut Ui, token: &str) {
+        self.repositories.set_client(self.client.clone());
         self.repositories.paint(ui, token);
     }
 
     pub fn paint_pull_requests(&mut self, ui: &mut Ui, token: &str) {
         self.pull_requests.set_repositories(self.repositories.repositories());
+        self.pull_requests.set_client(self.client.clone());
         self.pull_requests.paint(ui, token);
     }
 
     pub fn paint_deployments(&mut self, ui: &mut Ui, token: &str) {
         self.deployment.set_repositories(self.repositories.repositories());
+        self.deployment.set_client(self.client.clone());
         self.deployment.paint(ui, token);
     }
 
     pub fn paint_workflows(&mut self, ui: &mut Ui, token: &str) {
         self.workflow.set_repositories(self.repositories.repositories());
+        self.workflow.set_client(self.client.clone());
         self.workflow.paint(ui, token);
     }
+
+    pub fn paint_activity(&mut self, ui: &mut Ui, token: &str) {
+        self.activity.set_client(self.client.clone());
+        self.activity.paint(ui, token);
+    }
 }
 
 pub trait Panel {
@@ -77,3 +118,18 @@ pub trait Panel {
     fn set_client(&mut self, client: Client);
     fn paint(&mut self, ui: &mut Ui, token: &str);
 }
+
+#[cfg(test)]
+mod persisted {
+    use crate::panel::Panels;
+
+    #[test]
+    fn client_stored_once_without_cache() {
+        let stored = serde_json::to_value(Panels::default()).unwrap();
+        assert!(stored.get("client").is_some());
+        assert!(stored["client"].get("cache").is_none(), "responses are only stored sealed, with the token");
+        ["repositories", "pull_requests", "deployment", "workflow", "activity"].into_iter().for_each(|panel| {
+            assert!(stored[panel].get("client").is_none(), "{panel} stores its own client");
+        });
+    }
+}
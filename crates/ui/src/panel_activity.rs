@@ -0,0 +1,103 @@
+use egui::{Color32, Ui};
+use egui_extras::TableBuilder;
+use serde::{Deserialize, Serialize};
+
+use http::activity::{Activity, CacheUse};
+use http::github;
+use model::repository::Repository;
+
+use crate::{FixedField, Scroll, Scrollbar, Table};
+use crate::panel::Panel;
+use crate::timestamp;
+
+/// Every request the client made, so an empty table can be explained.
+#[derive(Deserialize, Serialize, Default)]
+pub struct ActivityPanel {
+    errors_only: bool,
+    #[serde(skip)]
+    client: github::Client,
+}
+
+impl Panel for ActivityPanel {
+    fn set_repositories(&mut self, _: Vec<Repository>) {}
+    fn set_client(&mut self, client: github::Client) { self.client = client }
+
+    fn paint(&mut self, ui: &mut Ui, _: &str) {
+        ui.heading("Activity");
+
+        let entries = self.client.activity().entries();
+        ui.horizontal_wrapped(|ui| {
+            let failed = entries.iter().filter(|activity| activity.is_error()).count();
+            ui.label(format!("{} requests, {failed} failed", entries.len()));
+            ui.checkbox(&mut self.errors_only, "Only errors");
+            if ui.button("Clear").clicked() {
+                self.client.activity().clear();
+            }
+        });
+
+        FixedField::minimum_width(100.0, ui, |ui| {
+            Scrollbar::horizontal(ui, |ui| {
+                let columns = vec!["Time", "Method", "URL", "Status", "Duration", "Cost", "Cache", "Error"];
+                TableBuilder::create(ui, columns).body(|mut body| {
+                    visible(entries, self.errors_only).into_iter().for_each(|activity| {
+                        body.row(18.0, |mut row| {
+                            row.col(|ui| { ui.label(timestamp::clock(activity.started, "%H:%M:%S")); });
+                            row.col(|ui| { ui.label(&activity.method); });
+                            row.col(|ui| { ui.label(&activity.url); });
+                            row.col(|ui| {
+                                let status = activity.status.map(|it| it.to_string()).unwrap_or_default();
+                                match activity.is_error() {
+                                    true => ui.colored_label(Color32::LIGHT_RED, status),
+                                    false => ui.label(status),
+                                };
+                            });
+                            row.col(|ui| { ui.label(format!("{} ms", activity.duration.num_milliseconds())); });
+                            row.col(|ui| { ui.label(activity.cost.map(|it| it.to_string()).unwrap_or_default()); });
+                            row.col(|ui| { ui.label(cache(activity.cache)); });
+                            row.col(|ui| { ui.colored_label(Color32::LIGHT_RED, activity.error.unwrap_or_default()); });
+                        });
+                    });
+                });
+            });
+        });
+    }
+}
+
+/// Newest first, leaving out the successful requests when only errors are wanted.
+fn visible(entries: Vec<Activity>, errors_only: bool) -> Vec<Activity> {
+    entries.into_iter().rev().filter(|activity| !errors_only || activity.is_error()).collect()
+}
+
+fn cache(cache: CacheUse) -> &'static str {
+    match cache {
+        CacheUse::Hit => "hit",
+        CacheUse::Miss => "miss",
+        CacheUse::Uncached => "",
+    }
+}
+
+#[cfg(test)]
+mod filter {
+    use std::sync::Arc;
+
+    use http::github::Client;
+    use http::retry::RetryPolicy;
+    use http::transport::FakeTransport;
+
+    use crate::panel_activity::visible;
+
+    #[test]
+    fn errors_only_newest_first() {
+        let mut client = Client::default().with_transport(Arc::new(FakeTransport::default()
+            .respond("*/repos/navikt/api/pulls*", 200, "[]")
+            .respond("*/repos/navikt/gone/pulls*", 404, r#"{"message":"Not Found"}"#)
+            .respond("*/repos/navikt/private/pulls*", 403, r#"{"message":"Resource not accessible by integration"}"#)));
+        client.set_retry_policy(RetryPolicy::disabled());
+        ["api", "gone", "private"].iter().for_each(|repo| client.get_path("token", &format!("/repos/navikt/{repo}/pulls"), |_| {}));
+
+        let entries = client.activity().entries();
+        assert_eq!(visible(entries.clone(), false).len(), 3);
+        let errors = visible(entries, true).into_iter().map(|it| it.error.unwrap()).collect::<Vec<_>>();
+        assert_eq!(errors, vec!["403: Resource not accessible by integration", "404: Not Found"]);
+    }
+}
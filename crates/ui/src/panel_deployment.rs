@@ -6,21 +6,37 @@ use egui::{Color32, Ui};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
+use http::error::GitHubError;
 use http::github;
+use http::pagination::Limit;
+use http::settings::Settings;
 use model::deployment::{Deployment, State, Status};
-use model::environment::{Environment, Environments};
+use model::environment::Environment;
+use model::graphql::{self, Batch, Deployments, LatestDeployments};
 use model::repository::Repository;
 
+use crate::auto_refresh::{AutoRefresh, AutoRefreshing};
+use crate::fetch_state::{Fetches, GRAPHQL};
 use crate::panel::Panel;
+use crate::timestamp;
 use crate::{FixedField, Scroll, Scrollbar};
 
+/// Deployments are listed newest first, so this covers the latest deployment of every environment.
+const DEPLOYMENTS_PER_REPOSITORY: usize = 100;
+
 #[derive(Deserialize, Serialize, Default)]
 pub struct DeploymentPanel {
     repositories: Vec<Repository>,
     deployments: Arc<Mutex<BTreeMap<String, Vec<Deployment>>>>,
     statuses: Arc<Mutex<BTreeMap<i64, Status>>>,
     environments: Arc<Mutex<Vec<Environment>>>,
+    #[serde(skip)]
     client: github::Client,
+    /// Per repository, and per repository and environment for the statuses.
+    #[serde(skip)]
+    fetches: Fetches,
+    #[serde(default)]
+    auto_refresh: AutoRefresh,
 }
 
 impl Panel for DeploymentPanel {
@@ -38,6 +54,9 @@ impl Panel for DeploymentPanel {
             if ui.button("Refresh deployments").clicked() {
                 self.refresh_statuses(token);
             }
+
+            self.auto_refresh.paint(ui);
+            self.fetches.paint_summary(ui);
         });
 
         ui.horizontal_top(|ui| {
@@ -51,19 +70,21 @@ impl Panel for DeploymentPanel {
                                         ui.vertical(|ui| {
                                             ui.heading(&env.name);
                                             self.repositories().for_each(|repository| {
-                                                if let Some(deployment) =
-                                                    self.deployment(&repository.name, &env.name)
-                                                {
-                                                    if let Some(status) = self
-                                                        .statuses
-                                                        .lock()
-                                                        .unwrap()
-                                                        .get(&deployment.id)
-                                                    {
-                                                        ui.horizontal_wrapped(|ui| {
-                                                            FixedField::height(150.0, ui, |ui| {
-                                                                ui.label(&repository.name.clone());
-                                                            });
+                                                let deployment = self.deployment(&repository.name, &env.name);
+                                                let status = deployment.as_ref()
+                                                    .and_then(|deployment| self.statuses.lock().unwrap().get(&deployment.id).cloned());
+                                                let status_key = status_key(&repository.name, &env.name);
+                                                let failed = self.fetches.failures().into_iter()
+                                                    .any(|(key, _)| key == repository.name || key == status_key);
+
+                                                if status.is_some() || failed {
+                                                    ui.horizontal_wrapped(|ui| {
+                                                        FixedField::height(150.0, ui, |ui| {
+                                                            ui.label(repository.name.clone());
+                                                            self.fetches.paint_badge(ui, &repository.name);
+                                                            self.fetches.paint_badge(ui, &status_key);
+                                                        });
+                                                        if let Some(status) = status {
                                                             FixedField::height(60.0, ui, |ui| {
                                                                 ui.colored_label(
                                                                     status.color(),
@@ -73,8 +94,13 @@ impl Panel for DeploymentPanel {
                                                             FixedField::height(200.0, ui, |ui| {
                                                                 ui.label(status.description());
                                                             });
-                                                        });
-                                                    }
+                                                        }
+                                                        if let Some(deployment) = deployment {
+                                                            FixedField::height(80.0, ui, |ui| {
+                                                                timestamp::paint(ui, deployment.created_at);
+                                                            });
+                                                        }
+                                                    });
                                                 }
                                             });
                                         });
@@ -90,6 +116,12 @@ impl Panel for DeploymentPanel {
 
 }
 
+impl AutoRefreshing for DeploymentPanel {
+    fn auto_refresh(&mut self) -> &mut AutoRefresh { &mut self.auto_refresh }
+    /// Environments and deployments, the GraphQL query brings their statuses along.
+    fn refresh_now(&mut self, token: &str) { self.refresh_deployments(token) }
+}
+
 trait StateColor {
     fn color(&self) -> Color32;
 }
@@ -104,10 +136,22 @@ impl StateColor for Status {
             State::Success => Color32::LIGHT_GREEN,
             State::Queued => Color32::LIGHT_BLUE,
             State::InProgress => Color32::LIGHT_RED,
+            State::Unknown(_) => Color32::LIGHT_GRAY,
         }
     }
 }
 
+/// The newest deployment for each environment by creation time, the higher id breaking ties.
+fn latest_per_environment(deployments: Vec<Deployment>) -> Vec<Deployment> {
+    deployments
+        .into_iter()
+        .into_group_map_by(|deployment| deployment.environment.clone())
+        .into_values()
+        .filter_map(|group| group.into_iter().max_by_key(|deployment| (deployment.created_at, deployment.id)))
+        .sorted_by(|a, b| a.environment.cmp(&b.environment))
+        .collect_vec()
+}
+
 impl DeploymentPanel {
     fn repositories(&self) -> IntoIter<Repository> {
         self.repositories.clone().into_iter()
@@ -132,10 +176,10 @@ impl DeploymentPanel {
     fn refresh_statuses(&mut self, token: &str) {
         self.statuses.lock().unwrap().clear();
         let repo_to_deployments = self.deployments.lock().unwrap().clone();
-        repo_to_deployments.into_values().for_each(|deployments| {
+        repo_to_deployments.into_iter().for_each(|(repository, deployments)| {
             deployments.into_iter().for_each(|deployment| {
                 let statuses = self.statuses.clone();
-                self.refresh_status(token, &deployment, move |response| {
+                let on_refreshed = self.fetches.track(&status_key(&repository, &deployment.environment), move |response: Vec<Status>| {
                     let last_status = response
                         .into_iter()
                         .sorted_by(|cur, next| Ord::cmp(&next.id, &cur.id))
@@ -145,108 +189,314 @@ impl DeploymentPanel {
                         statuses.lock().unwrap().insert(deployment.id, status);
                     }
                 });
+                self.refresh_status(token, &deployment, on_refreshed);
             });
         });
     }
 
+    fn refresh_status(
+        &mut self,
+        token: &str,
+        deployment: &Deployment,
+        on_refreshed: impl FnOnce(Result<Vec<Status>, GitHubError>) + Send + 'static,
+    ) {
+        self.client.get_all::<Status>(token, &deployment.statuses_url, Limit::pages(1), on_refreshed)
+    }
+
+    /// Environments, latest deployments and their statuses in a few GraphQL queries, falling back to the REST API
+    /// for the repositories of a failed query and those with more environments than the query fetches.
     fn refresh_deployments(&mut self, token: &str) {
         self.environments.lock().unwrap().clear();
         self.deployments.lock().unwrap().clear();
+        self.fetches.clear();
 
-        self.repositories().for_each(|repository| {
+        let names = self.repositories().map(|repository| repository.name).collect_vec();
+        let queries = graphql::deployments_queries(&self.client.settings().org, &names);
+        for (query, repositories) in queries.into_iter().zip(self.repositories.chunks(graphql::BATCH_SIZE)) {
+            let repositories = repositories.to_vec();
+            repositories.iter().for_each(|repository| self.fetches.start(&repository.name));
+            let fetches = self.fetches.clone();
             let environments = self.environments.clone();
-            self.refresh_environment(token, &repository, move |response| {
-                let new_envs = response
-                    .into_iter()
-                    .filter(|env| {
-                        !environments
-                            .lock()
-                            .unwrap()
-                            .iter()
-                            .any(|cur| cur.name == env.name)
-                    })
-                    .collect_vec();
-
-                environments.lock().unwrap().extend(new_envs);
-            });
-
-            let repository_name = repository.clone().name;
             let deployments = self.deployments.clone();
-            self.refresh_deployment(token, &repository, move |response| {
-                let last_deployments_per_environment = response
-                    .into_iter()
-                    .group_by(|deps| deps.environment.clone())
-                    .into_iter()
-                    .map(|(_, group)| group.max_by(|x, y| x.id.cmp(&y.id)).unwrap())
-                    .collect_vec();
-
-                *deployments
-                    .lock()
-                    .unwrap()
-                    .entry(repository_name)
-                    .or_default() = last_deployments_per_environment;
+            let statuses = self.statuses.clone();
+            let mut client = self.client.clone();
+            let token = token.to_string();
+            self.client.graphql::<Batch<Deployments>>(&token.clone(), &query, move |response| match response {
+                Ok(batch) => {
+                    let found = batch.repositories.into_values().flatten().collect_vec();
+                    repositories.iter().for_each(|repository| match found.iter().find(|it| it.name == repository.name) {
+                        Some(found) if !found.has_more_environments() => {
+                            let deployments_url = deployments_url(client.settings(), &found.name);
+                            add_environments(&environments, found.environments());
+                            add_deployments(&deployments, &statuses, &found.name, found.deployments(&deployments_url));
+
+                            let missing = found.missing_environments();
+                            match missing.is_empty() {
+                                true => fetches.loaded(&found.name),
+                                false => refresh_latest_deployments(&mut client, &token, &found.name, &missing, deployments.clone(), statuses.clone(), &fetches),
+                            }
+                        }
+                        // not in the result, or with more environments than the query fetches
+                        _ => refresh_repository(&mut client, &token, repository, environments.clone(), deployments.clone(), &fetches),
+                    });
+                }
+                Err(e) => {
+                    fetches.failed(GRAPHQL, format!("{e}, fetched per repository instead"));
+                    repositories.iter().for_each(|repository| {
+                        refresh_repository(&mut client, &token, repository, environments.clone(), deployments.clone(), &fetches)
+                    });
+                }
             });
-        });
+        }
     }
+}
 
-    fn refresh_deployment(
-        &mut self,
-        token: &str,
-        repo: &Repository,
-        on_refreshed: impl FnOnce(Vec<Deployment>) + Send + 'static,
-    ) {
-        let mut client = self.client.clone();
-        self.client.get(token, &repo.deployments_url.clone(), move |response| {
-            if let Ok(response) = response {
-                if let Some(remaining) = response.headers.get("x-ratelimit-remaining") {
-                    let remaining = remaining.parse::<usize>().unwrap();
-                    client.set_rate_limit(remaining);
-                }
-                
-                let deployments = serde_json::from_slice::<Vec<Deployment>>(&response.bytes).unwrap_or_default();
-                on_refreshed(deployments);
-            }
-        })
+fn add_environments(environments: &Mutex<Vec<Environment>>, response: Vec<Environment>) {
+    let mut environments = environments.lock().unwrap();
+    let new_envs = response
+        .into_iter()
+        .filter(|env| !environments.iter().any(|cur| cur.name == env.name))
+        .collect_vec();
+
+    environments.extend(new_envs);
+}
+
+/// Keeps the latest of `response` and the deployments already known per environment, with the statuses that came along.
+fn add_deployments(
+    deployments: &Mutex<BTreeMap<String, Vec<Deployment>>>,
+    statuses: &Mutex<BTreeMap<i64, Status>>,
+    repository: &str,
+    response: Vec<(Deployment, Option<Status>)>,
+) {
+    let (response, response_statuses): (Vec<_>, Vec<_>) = response.into_iter().unzip();
+    response.iter().zip(response_statuses).for_each(|(deployment, status)| {
+        if let Some(status) = status {
+            statuses.lock().unwrap().insert(deployment.id, status);
+        }
+    });
+
+    let mut deployments = deployments.lock().unwrap();
+    let known = deployments.entry(repository.to_string()).or_default();
+    let all = std::mem::take(known).into_iter().chain(response).collect_vec();
+    *known = latest_per_environment(all);
+}
+
+/// The latest deployment to each of `environments`, which were not among the newest deployments of the repository.
+fn refresh_latest_deployments(
+    client: &mut github::Client,
+    token: &str,
+    repository: &str,
+    environments: &[String],
+    deployments: Arc<Mutex<BTreeMap<String, Vec<Deployment>>>>,
+    statuses: Arc<Mutex<BTreeMap<i64, Status>>>,
+    fetches: &Fetches,
+) {
+    let query = graphql::latest_deployments_query(&client.settings().org, repository, environments);
+    let repository_name = repository.to_string();
+    let deployments_url = deployments_url(client.settings(), repository);
+    client.graphql::<LatestDeployments>(token, &query, fetches.track(repository, move |latest: LatestDeployments| {
+        add_deployments(&deployments, &statuses, &repository_name, latest.deployments(&deployments_url));
+    }));
+}
+
+fn refresh_repository(
+    client: &mut github::Client,
+    token: &str,
+    repository: &Repository,
+    environments: Arc<Mutex<Vec<Environment>>>,
+    deployments: Arc<Mutex<BTreeMap<String, Vec<Deployment>>>>,
+    fetches: &Fetches,
+) {
+    let key = format!("{} environments", repository.name);
+    refresh_environment(client, token, repository, fetches.track(&key, move |response| {
+        add_environments(&environments, response);
+    }));
+
+    let repository_name = repository.clone().name;
+    refresh_deployment(client, token, repository, fetches.track(&repository.name, move |response| {
+        *deployments
+            .lock()
+            .unwrap()
+            .entry(repository_name)
+            .or_default() = latest_per_environment(response);
+    }));
+}
+
+fn refresh_deployment(
+    client: &mut github::Client,
+    token: &str,
+    repo: &Repository,
+    on_refreshed: impl FnOnce(Result<Vec<Deployment>, GitHubError>) + Send + 'static,
+) {
+    client.get_all::<Deployment>(token, &repo.deployments_url, Limit::items(DEPLOYMENTS_PER_REPOSITORY), on_refreshed)
+}
+
+fn refresh_environment(
+    client: &mut github::Client,
+    token: &str,
+    repository: &Repository,
+    on_refreshed: impl FnOnce(Result<Vec<Environment>, GitHubError>) + Send + 'static,
+) {
+    client.get_all_path::<Environment>(
+        token,
+        &format!("/repos/{}/environments", repository.full_name),
+        Limit::default(),
+        on_refreshed,
+    );
+}
+
+/// REST listing of a repository's deployments, what the deployments from GraphQL link their statuses from.
+fn deployments_url(settings: &Settings, repository: &str) -> String {
+    settings.api(&format!("/repos/{}/{repository}/deployments", settings.org))
+}
+
+/// Fetch state key of the statuses of a repository's deployment to an environment.
+fn status_key(repository: &str, environment: &str) -> String {
+    format!("{repository} in {environment}")
+}
+
+#[cfg(test)]
+mod refresh {
+    use std::sync::Arc;
+
+    use http::github::Client;
+    use http::transport::FakeTransport;
+    use model::repository::Repository;
+
+    use crate::panel::Panel;
+    use crate::panel_deployment::DeploymentPanel;
+
+    fn deployment(id: i64, environment: &str) -> String {
+        format!(r#"{{"url":"","id":{id},"task":"deploy","environment":"{environment}","created_at":"2023-02-01T10:00:00Z","updated_at":"2023-02-01T10:00:00Z","statuses_url":"https://api.github.com/repos/navikt/api/deployments/{id}/statuses"}}"#)
     }
 
-    fn refresh_status(
-        &mut self,
-        token: &str,
-        deployment: &Deployment,
-        on_refreshed: impl FnOnce(Vec<Status>) + Send + 'static,
-    ) {
-        let mut client = self.client.clone();
-        self.client.get(token, &deployment.statuses_url, move |response| {
-            if let Ok(response) = response {
-                if let Some(remaining) = response.headers.get("x-ratelimit-remaining") {
-                    let remaining = remaining.parse::<usize>().unwrap();
-                    client.set_rate_limit(remaining);
-                }
+    fn status(id: i64, state: &str) -> String {
+        format!(r#"{{"url":"","id":{id},"state":"{state}","description":"{state}"}}"#)
+    }
 
-                let statuses = serde_json::from_slice::<Vec<Status>>(&response.bytes).unwrap_or_default();
-                on_refreshed(statuses)
-            }
-        })
+    /// The REST API, answering after `transport`.
+    fn rest(transport: FakeTransport) -> FakeTransport {
+        transport
+            .respond("https://api.github.com/repos/navikt/api/environments*", 200, r#"{"total_count":2,"environments":[{"url":"","id":1,"name":"dev","html_url":""},{"url":"","id":2,"name":"prod","html_url":""}]}"#)
+            .respond("https://api.github.com/repos/navikt/api/deployments/5/statuses*", 200, &format!("[{},{}]", status(51, "success"), status(50, "in_progress")))
+            .respond("https://api.github.com/repos/navikt/api/deployments/4/statuses*", 200, &format!("[{}]", status(40, "failure")))
+            .respond("https://api.github.com/repos/navikt/api/deployments*", 200, &format!("[{},{},{},{}]", deployment(5, "prod"), deployment(4, "dev"), deployment(3, "prod"), deployment(2, "dev")))
     }
 
-    fn refresh_environment(
-        &mut self,
-        token: &str,
-        repository: &Repository,
-        on_refreshed: impl FnOnce(Vec<Environment>) + Send + 'static,
-    ) {
-        self.client.get_path(
-            token,
-            &format!("/repos/navikt/{}/environments", repository.name),
-            |response| {
-                if let Ok(response) = response {
-                    let environments = match serde_json::from_slice::<Environments>(&response.bytes) {
-                        Ok(environments) => environments.environments,
-                        Err(_) => vec![],
-                    };
-                    on_refreshed(environments);
-                }
-            },
-        );
+    fn panel() -> DeploymentPanel {
+        panel_with(rest(FakeTransport::default()))
+    }
+
+    fn panel_with(transport: FakeTransport) -> DeploymentPanel {
+        let repository = r#"{"id":1,"name":"api","full_name":"navikt/api","html_url":"","deployments_url":"https://api.github.com/repos/navikt/api/deployments","releases_url":"","pulls_url":"","description":null,"archived":false}"#;
+        let mut panel = DeploymentPanel::default();
+        panel.set_client(Client::default().with_transport(Arc::new(transport)));
+        panel.set_repositories(vec![serde_json::from_str::<Repository>(repository).unwrap()]);
+        panel
+    }
+
+    #[test]
+    fn graphql() {
+        let data = r#"{"data":{"rateLimit":{"cost":1,"limit":5000,"remaining":4999,"resetAt":"2023-02-01T11:00:00Z"},"r0":{"name":"api",
+            "environments":{"nodes":[{"databaseId":1,"name":"dev"},{"databaseId":2,"name":"prod"}]},
+            "deployments":{"nodes":[
+                {"databaseId":5,"environment":"prod","task":"deploy","createdAt":"2023-02-01T12:00:00Z","updatedAt":"2023-02-01T12:00:00Z","latestStatus":{"state":"SUCCESS","description":null}},
+                {"databaseId":4,"environment":"dev","task":"deploy","createdAt":"2023-02-01T11:00:00Z","updatedAt":"2023-02-01T11:00:00Z","latestStatus":{"state":"FAILURE","description":"Crashed"}},
+                {"databaseId":3,"environment":"prod","task":"deploy","createdAt":"2023-02-01T10:00:00Z","updatedAt":"2023-02-01T10:00:00Z","latestStatus":null}]}}}}"#;
+        let transport = Arc::new(FakeTransport::default().respond("https://api.github.com/graphql", 200, data));
+        let mut panel = panel();
+        panel.set_client(Client::default().with_transport(transport.clone()));
+        panel.refresh_deployments("token");
+
+        assert_eq!(transport.requests(), vec!["https://api.github.com/graphql"]);
+        assert_eq!(panel.environments().map(|env| env.name).collect::<Vec<_>>(), vec!["dev", "prod"]);
+        assert_eq!(panel.deployment("api", "prod").unwrap().id, 5);
+        assert_eq!(panel.statuses.lock().unwrap().get(&4).unwrap().description(), "Crashed");
+    }
+
+    #[test]
+    fn statuses_after_graphql() {
+        let data = r#"{"data":{"rateLimit":{"cost":1,"limit":5000,"remaining":4999,"resetAt":"2023-02-01T11:00:00Z"},"r0":{"name":"api",
+            "environments":{"nodes":[{"databaseId":1,"name":"dev"},{"databaseId":2,"name":"prod"}]},
+            "deployments":{"nodes":[
+                {"databaseId":5,"environment":"prod","task":"deploy","createdAt":"2023-02-01T12:00:00Z","updatedAt":"2023-02-01T12:00:00Z","latestStatus":{"state":"IN_PROGRESS","description":null}},
+                {"databaseId":4,"environment":"dev","task":"deploy","createdAt":"2023-02-01T11:00:00Z","updatedAt":"2023-02-01T11:00:00Z","latestStatus":null}]}}}}"#;
+        let transport = Arc::new(FakeTransport::default()
+            .respond("https://api.github.com/graphql", 200, data)
+            .respond("https://api.github.com/repos/navikt/api/deployments/5/statuses*", 200, &format!("[{}]", status(51, "success")))
+            .respond("https://api.github.com/repos/navikt/api/deployments/4/statuses*", 200, &format!("[{}]", status(40, "failure"))));
+        let mut panel = panel();
+        panel.set_client(Client::default().with_transport(transport.clone()));
+        panel.refresh_deployments("token");
+        panel.refresh_statuses("token");
+
+        let statuses = panel.statuses.lock().unwrap().clone();
+        assert_eq!(statuses.get(&5).unwrap().id, 51);
+        assert_eq!(statuses.get(&4).unwrap().id, 40);
+        assert!(panel.fetches.failures().is_empty());
+    }
+
+    #[test]
+    fn prod_beyond_the_first_page() {
+        let data = r#"{"data":{"rateLimit":{"cost":1,"limit":5000,"remaining":4999,"resetAt":"2023-02-01T11:00:00Z"},"r0":{"name":"api",
+            "environments":{"nodes":[{"databaseId":1,"name":"dev"},{"databaseId":2,"name":"prod"}]},
+            "deployments":{"nodes":[
+                {"databaseId":9,"environment":"dev","task":"deploy","createdAt":"2023-02-01T12:00:00Z","updatedAt":"2023-02-01T12:00:00Z","latestStatus":null}],
+                "pageInfo":{"hasNextPage":true}}}}}"#;
+        let latest = r#"{"data":{"rateLimit":{"cost":1,"limit":5000,"remaining":4998,"resetAt":"2023-02-01T11:00:00Z"},"repository":{
+            "e0":{"nodes":[{"databaseId":3,"environment":"prod","task":"deploy","createdAt":"2023-01-01T10:00:00Z","updatedAt":"2023-01-01T10:00:00Z","latestStatus":{"state":"SUCCESS","description":null}}]}}}}"#;
+        let transport = Arc::new(FakeTransport::default()
+            .respond("https://api.github.com/graphql", 200, data)
+            .respond("https://api.github.com/graphql", 200, latest));
+        let mut panel = panel();
+        panel.set_client(Client::default().with_transport(transport.clone()));
+        panel.refresh_deployments("token");
+
+        assert_eq!(transport.requests().len(), 2);
+        assert_eq!(panel.deployment("api", "dev").unwrap().id, 9);
+        assert_eq!(panel.deployment("api", "prod").unwrap().id, 3);
+        assert!(panel.statuses.lock().unwrap().contains_key(&3));
+    }
+
+    #[test]
+    fn rest_for_what_graphql_left_out() {
+        let more_environments = r#"{"data":{"rateLimit":{"cost":1,"limit":5000,"remaining":4999,"resetAt":"2023-02-01T11:00:00Z"},"r0":{"name":"api",
+            "environments":{"nodes":[{"databaseId":1,"name":"dev"}],"pageInfo":{"hasNextPage":true}},
+            "deployments":{"nodes":[],"pageInfo":{"hasNextPage":false}}}}}"#;
+        let not_resolved = r#"{"data":{"rateLimit":{"cost":1,"limit":5000,"remaining":4999,"resetAt":"2023-02-01T11:00:00Z"},"r0":null},"errors":[{"message":"Could not resolve to a Repository with the name 'navikt/api'."}]}"#;
+        [more_environments, not_resolved].into_iter().for_each(|data| {
+            let mut panel = panel_with(rest(FakeTransport::default().respond("https://api.github.com/graphql", 200, data)));
+            panel.refresh_deployments("token");
+            assert_eq!(panel.environments().map(|env| env.name).collect::<Vec<_>>(), vec!["dev", "prod"]);
+            assert_eq!(panel.deployment("api", "prod").unwrap().id, 5);
+            assert!(panel.fetches.failures().is_empty());
+        });
+    }
+
+    #[test]
+    fn environments() {
+        let mut panel = panel();
+        panel.refresh_deployments("token");
+        let names = panel.environments().map(|env| env.name).collect::<Vec<_>>();
+        assert_eq!(names, vec!["dev", "prod"]);
+    }
+
+    #[test]
+    fn latest_deployment_per_environment() {
+        let mut panel = panel();
+        panel.refresh_deployments("token");
+        assert_eq!(panel.deployments.lock().unwrap().get("api").unwrap().len(), 2);
+        assert_eq!(panel.deployment("api", "prod").unwrap().id, 5);
+        assert_eq!(panel.deployment("api", "dev").unwrap().id, 4);
+    }
+
+    #[test]
+    fn latest_status_per_deployment() {
+        let mut panel = panel();
+        panel.refresh_deployments("token");
+        panel.refresh_statuses("token");
+        let statuses = panel.statuses.lock().unwrap().clone();
+        assert_eq!(statuses.get(&5).unwrap().id, 51);
+        assert_eq!(statuses.get(&4).unwrap().id, 40);
     }
 }
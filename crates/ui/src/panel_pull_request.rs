@@ -1,22 +1,37 @@
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
 use std::sync::{Arc, Mutex};
 use std::vec::IntoIter;
 
-use egui::Ui;
+use egui::{Color32, Ui};
 use egui_extras::TableBuilder;
 use serde::{Deserialize, Serialize};
 
 use http::github;
+use http::pagination::Limit;
+use model::graphql::{self, Batch, PullRequests};
 use model::pull_request::PullRequest;
 use model::repository::Repository;
 
 use crate::{FixedField, Scroll, Scrollbar, Table};
+use crate::auto_refresh::{AutoRefresh, AutoRefreshing};
+use crate::fetch_state::{Fetches, GRAPHQL};
 use crate::panel::Panel;
+use crate::timestamp;
 
 #[derive(Deserialize, Serialize, Default)]
 pub struct PullRequestsPanel {
     repositories: Vec<Repository>,
-    pull_requests: Arc<Mutex<Vec<PullRequest>>>,
+    /// Per repository, replaced by each fetch so overlapping refreshes do not add up.
+    #[serde(default)]
+    repository_pull_requests: Arc<Mutex<BTreeMap<String, Vec<PullRequest>>>>,
+    #[serde(skip)]
     client: github::Client,
+    /// Per repository.
+    #[serde(skip)]
+    fetches: Fetches,
+    #[serde(default)]
+    auto_refresh: AutoRefresh,
 }
 
 impl Panel for PullRequestsPanel {
@@ -26,35 +41,36 @@ impl Panel for PullRequestsPanel {
     fn paint(&mut self, ui: &mut Ui, token: &str) {
         ui.heading("Pull Requests");
 
-        if ui.button("Refresh").clicked() {
-            self.clear_pull_requests();
-            self.repositories().for_each(|repo| {
-                let _pulls = self.pull_requests.clone();
-                let mut client = self.client.clone();
-                self.client.get(token, repo.pulls_url(), move |response| {
-                    if let Ok(response) = response {
-                        
-                        if let Some(remaining) = response.headers.get("x-ratelimit-remaining") {
-                            let remaining = remaining.parse::<usize>().unwrap();
-                            client.set_rate_limit(remaining);
-                        }
-
-                        let pull_requests = serde_json::from_slice::<Vec<PullRequest>>(&response.bytes).unwrap_or_default(); 
-                        _pulls.lock().unwrap().extend(pull_requests);
-                    }
-                });
-            })
-        }
+        ui.horizontal_wrapped(|ui| {
+            if ui.button("Refresh").clicked() {
+                self.refresh(token);
+            }
+            self.auto_refresh.paint(ui);
+            self.fetches.paint_summary(ui);
+        });
 
         FixedField::minimum_width(100.0, ui, |ui| {
             Scrollbar::horizontal(ui, |ui| {
                 TableBuilder::create(ui, vec!["Repo", "Title", "Author", "Last Update"]).body(|mut body| {
                     self.pull_requests().for_each(|pull| {
                         body.row(18.0, |mut row| {
-                            row.col(|ui| { ui.label(&pull.repo()); });
-                            row.col(|ui| { ui.hyperlink_to(&pull.title(), &pull.html()); });
-                            row.col(|ui| { ui.label(&pull.updated_at()); });
-                            row.col(|ui| { ui.label(&pull.user()); });
+                            row.col(|ui| { ui.label(pull.repo()); });
+                            row.col(|ui| { ui.hyperlink_to(pull.title(), pull.html()); });
+                            row.col(|ui| { ui.label(pull.user()); });
+                            row.col(|ui| { timestamp::paint_optional(ui, pull.updated_at()); });
+                        });
+                    });
+
+                    // repositories that failed have no pull requests to show the error on
+                    self.fetches.failures().into_iter().filter(|(key, _)| key != GRAPHQL).for_each(|(repo, reason)| {
+                        body.row(18.0, |mut row| {
+                            row.col(|ui| {
+                                ui.label(&repo);
+                                self.fetches.paint_badge(ui, &repo);
+                            });
+                            row.col(|ui| { ui.colored_label(Color32::LIGHT_RED, reason); });
+                            row.col(|_| {});
+                            row.col(|_| {});
                         });
                     });
                 });
@@ -63,18 +79,189 @@ impl Panel for PullRequestsPanel {
     }
 }
 
-// fn path(repo: &str) -> &str { &format!("/repos/navikt/{}/pulls", repo) }
+impl AutoRefreshing for PullRequestsPanel {
+    fn auto_refresh(&mut self) -> &mut AutoRefresh { &mut self.auto_refresh }
+    fn refresh_now(&mut self, token: &str) { self.refresh(token) }
+}
 
 impl PullRequestsPanel {
+    /// Open pull requests in a few GraphQL queries, falling back to the REST API
+    /// for the repositories of a failed query and those the query did not fetch completely.
+    fn refresh(&mut self, token: &str) {
+        self.clear_pull_requests();
+        self.fetches.clear();
+
+        let names = self.repositories().map(|repo| repo.name).collect::<Vec<_>>();
+        let queries = graphql::pull_requests_queries(&self.client.settings().org, &names);
+        for (query, repositories) in queries.into_iter().zip(self.repositories.chunks(graphql::BATCH_SIZE)) {
+            let repositories = repositories.to_vec();
+            repositories.iter().for_each(|repo| self.fetches.start(&repo.name));
+            let pulls = self.repository_pull_requests.clone();
+            let fetches = self.fetches.clone();
+            let mut client = self.client.clone();
+            let token = token.to_string();
+            self.client.graphql::<Batch<PullRequests>>(&token.clone(), &query, move |response| match response {
+                Ok(batch) => {
+                    let found = batch.repositories.into_values().flatten().collect::<Vec<_>>();
+                    repositories.iter().for_each(|repo| match found.iter().find(|it| it.name == repo.name) {
+                        Some(found) if !found.has_more() => {
+                            pulls.lock().unwrap().insert(repo.name.clone(), found.clone().into_pull_requests());
+                            fetches.loaded(&repo.name);
+                        }
+                        // not in the result, or with more open pull requests than the query fetches
+                        _ => refresh_repository(&mut client, &token, repo, pulls.clone(), &fetches),
+                    });
+                }
+                Err(e) => {
+                    fetches.failed(GRAPHQL, format!("{e}, fetched per repository instead"));
+                    repositories.iter().for_each(|repo| refresh_repository(&mut client, &token, repo, pulls.clone(), &fetches));
+                }
+            });
+        }
+    }
+
     fn clear_pull_requests(&self) {
-        self.pull_requests.lock().unwrap().clear()
+        self.repository_pull_requests.lock().unwrap().clear()
     }
 
+    /// Most recently updated first, across repositories.
     fn pull_requests(&self) -> IntoIter<PullRequest> {
-        self.pull_requests.lock().unwrap().clone().into_iter()
+        let mut pull_requests = self.repository_pull_requests.lock().unwrap().values().flatten().cloned().collect::<Vec<_>>();
+        pull_requests.sort_by_key(|pull| Reverse(pull.updated_at()));
+        pull_requests.into_iter()
     }
 
     fn repositories(&self) -> IntoIter<Repository> {
         self.repositories.clone().into_iter()
     }
 }
+
+fn refresh_repository(client: &mut github::Client, token: &str, repo: &Repository, pulls: Arc<Mutex<BTreeMap<String, Vec<PullRequest>>>>, fetches: &Fetches) {
+    let name = repo.name.clone();
+    client.get_all::<PullRequest>(token, repo.pulls_url(), Limit::default(), fetches.track(&repo.name, move |pull_requests: Vec<PullRequest>| {
+        pulls.lock().unwrap().insert(name, pull_requests);
+    }));
+}
+
+#[cfg(test)]
+mod refresh {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use ehttp::Request;
+    use http::github::Client;
+    use http::transport::{FakeTransport, OnDone, Transport};
+    use model::repository::Repository;
+
+    use crate::fetch_state::{FetchState, GRAPHQL};
+    use crate::panel::Panel;
+    use crate::panel_pull_request::PullRequestsPanel;
+
+    /// Holds requests back until released, like responses that are still on their way.
+    #[derive(Default)]
+    struct Delayed {
+        inner: FakeTransport,
+        waiting: Mutex<Vec<(Request, OnDone)>>,
+    }
+
+    impl Delayed {
+        fn release(&self) {
+            let waiting = std::mem::take(&mut *self.waiting.lock().unwrap());
+            waiting.into_iter().for_each(|(request, on_done)| self.inner.fetch(request, on_done));
+        }
+    }
+
+    impl Transport for Delayed {
+        fn fetch(&self, request: Request, on_done: OnDone) {
+            self.waiting.lock().unwrap().push((request, on_done));
+        }
+
+        fn after(&self, delay: Duration, f: Box<dyn FnOnce() + Send>) {
+            self.inner.after(delay, f)
+        }
+    }
+
+    fn panel(transport: FakeTransport) -> PullRequestsPanel {
+        panel_with(Arc::new(transport))
+    }
+
+    fn panel_with(transport: Arc<dyn Transport>) -> PullRequestsPanel {
+        let repository = r#"{"id":1,"name":"api","full_name":"navikt/api","html_url":"","deployments_url":"","releases_url":"","pulls_url":"https://api.github.com/repos/navikt/api/pulls{/number}","description":null,"archived":false}"#;
+        let mut panel = PullRequestsPanel::default();
+        panel.set_client(Client::default().with_transport(transport));
+        panel.set_repositories(vec![serde_json::from_str::<Repository>(repository).unwrap()]);
+        panel
+    }
+
+    #[test]
+    fn graphql() {
+        let mut panel = panel(FakeTransport::default().respond("https://api.github.com/graphql", 200, r#"{"data":{"rateLimit":{"cost":1,"limit":5000,"remaining":4999,"resetAt":"2023-02-01T11:00:00Z"},
+            "r0":{"name":"api","pullRequests":{"nodes":[{"databaseId":7,"title":"Bump serde","url":"https://github.com/navikt/api/pull/7","updatedAt":"2023-02-01T10:00:00Z","author":{"login":"dependabot"}}]}}}}"#));
+        panel.refresh("token");
+        let pulls = panel.pull_requests().collect::<Vec<_>>();
+        assert_eq!(pulls.len(), 1);
+        assert_eq!(pulls[0].repo(), "api");
+        assert_eq!(pulls[0].user(), "dependabot");
+    }
+
+    #[test]
+    fn rest_fallback() {
+        let mut panel = panel(FakeTransport::default()
+            .respond("https://api.github.com/graphql", 502, "")
+            .respond("https://api.github.com/repos/navikt/api/pulls*", 200, r#"[{"id":8,"url":"","title":"Fix","head":{"repo":{"name":"api"}}}]"#));
+        panel.refresh("token");
+        let pulls = panel.pull_requests().collect::<Vec<_>>();
+        assert_eq!(pulls.len(), 1);
+        assert_eq!(pulls[0].id, 8);
+        assert!(matches!(panel.fetches.get("api"), FetchState::Loaded(_)));
+        assert!(matches!(panel.fetches.get(GRAPHQL), FetchState::Failed(reason) if reason.starts_with("502")));
+    }
+
+    #[test]
+    fn overlapping_refreshes() {
+        let transport = Arc::new(Delayed {
+            inner: FakeTransport::default()
+                .respond("https://api.github.com/graphql", 502, "")
+                .respond("https://api.github.com/repos/navikt/api/pulls*", 200, r#"[{"id":8,"url":"","title":"Fix","head":{"repo":{"name":"api"}}}]"#),
+            ..Delayed::default()
+        });
+        let mut panel = panel_with(transport.clone());
+        panel.client.set_retry_policy(http::retry::RetryPolicy::disabled());
+        panel.refresh("token");
+        panel.refresh("token");
+        while !transport.waiting.lock().unwrap().is_empty() {
+            transport.release();
+        }
+        assert_eq!(panel.pull_requests().count(), 1);
+    }
+
+    #[test]
+    fn more_than_one_page() {
+        let mut panel = panel(FakeTransport::default()
+            .respond("https://api.github.com/graphql", 200, r#"{"data":{"rateLimit":{"cost":1,"limit":5000,"remaining":4999,"resetAt":"2023-02-01T11:00:00Z"},
+                "r0":{"name":"api","pullRequests":{"nodes":[{"databaseId":7,"title":"Bump serde","url":"https://github.com/navikt/api/pull/7","updatedAt":"2023-02-01T10:00:00Z","author":null}],"pageInfo":{"hasNextPage":true}}}}}"#)
+            .respond("https://api.github.com/repos/navikt/api/pulls*", 200, r#"[{"id":7,"url":"","title":"Bump serde","head":{"repo":{"name":"api"}}},{"id":8,"url":"","title":"Fix","head":{"repo":{"name":"api"}}}]"#));
+        panel.refresh("token");
+        assert_eq!(panel.pull_requests().map(|pull| pull.id).collect::<Vec<_>>(), vec![7, 8]);
+    }
+
+    #[test]
+    fn not_in_the_result() {
+        let mut panel = panel(FakeTransport::default()
+            .respond("https://api.github.com/graphql", 200, r#"{"data":{"rateLimit":{"cost":1,"limit":5000,"remaining":4999,"resetAt":"2023-02-01T11:00:00Z"},"r0":null},"errors":[{"message":"Could not resolve to a Repository with the name 'navikt/api'."}]}"#)
+            .respond("https://api.github.com/repos/navikt/api/pulls*", 200, r#"[{"id":8,"url":"","title":"Fix","head":{"repo":{"name":"api"}}}]"#));
+        panel.refresh("token");
+        assert_eq!(panel.pull_requests().count(), 1);
+        assert!(matches!(panel.fetches.get("api"), FetchState::Loaded(_)));
+    }
+
+    #[test]
+    fn failed_repository() {
+        let mut panel = panel(FakeTransport::default()
+            .respond("https://api.github.com/graphql", 502, "")
+            .respond("https://api.github.com/repos/navikt/api/pulls*", 403, r#"{"message":"Resource not accessible by personal access token"}"#));
+        panel.refresh("token");
+        assert_eq!(panel.pull_requests().count(), 0);
+        assert_eq!(panel.fetches.get("api"), FetchState::Failed("403: Resource not accessible by personal access token".to_string()));
+    }
+}
@@ -3,14 +3,19 @@ use std::sync::{Arc, Mutex};
 use egui::{Color32, FontId, Ui};
 use egui::text::LayoutJob;
 use http::github::{Client, self};
-use itertools::Itertools;
+use http::pagination::Limit;
 use serde::{Deserialize, Serialize};
 
 use model::repository::Repository;
 use model::team::Team;
 
+use crate::fetch_state::Fetches;
 use crate::panel::Panel;
 
+/// Fetch state keys.
+const TEAM: &str = "team";
+const REPOSITORIES: &str = "repositories";
+
 #[derive(Deserialize, Serialize, Default)]
 pub struct RepositoriesPanel {
     repositories: Arc<Mutex<Vec<Repository>>>,
@@ -18,7 +23,10 @@ pub struct RepositoriesPanel {
     archived: Arc<Mutex<Vec<Repository>>>,
     team: Arc<Mutex<Option<Team>>>,
     team_name: String,
+    #[serde(skip)]
     client: Client,
+    #[serde(skip)]
+    fetches: Fetches,
 }
 
 impl Panel for RepositoriesPanel {
@@ -36,6 +44,9 @@ impl Panel for RepositoriesPanel {
             if ui.button("Select").clicked() {
                 self.fetch_team(token, self.team_name.clone());
             }
+            self.fetches.paint_badge(ui, TEAM);
+
+            self.fetches.paint_summary(ui);
         });
         ui.separator();
 
@@ -51,6 +62,14 @@ impl Panel for RepositoriesPanel {
     }
 }
 
+/// Archived and active repositories, leaving out the blacklisted ones.
+fn split_archived(repositories: Vec<Repository>, blacklisted: &[Repository]) -> (Vec<Repository>, Vec<Repository>) {
+    repositories
+        .into_iter()
+        .filter(|repo| !blacklisted.contains(repo))
+        .partition(|repo| repo.archived)
+}
+
 impl RepositoriesPanel {
     pub fn repositories(&self) -> Vec<Repository> {
         self.repositories.lock().unwrap().clone()
@@ -118,11 +137,15 @@ impl RepositoriesPanel {
         match team {
             Some(team) => {
                 ui.vertical(|ui| {
-                    ui.hyperlink_to("Members", format!("https://github.com/orgs/navikt/teams/{}/members", team.name));
+                    let settings = self.client.settings();
+                    ui.hyperlink_to("Members", settings.web(&format!("/orgs/{}/teams/{}/members", settings.org, team.slug)));
                     ui.label(team.description.unwrap_or_default());
-                    if ui.button("Fetch repositories").clicked() {
-                        self.fetch_repositories(token);
-                    }
+                    ui.horizontal(|ui| {
+                        if ui.button("Fetch repositories").clicked() {
+                            self.fetch_repositories(token);
+                        }
+                        self.fetches.paint_badge(ui, REPOSITORIES);
+                    });
                 });
             },
             None => {
@@ -133,19 +156,10 @@ impl RepositoriesPanel {
 
     fn fetch_team(&mut self, token: &str, team_name: String) {
         let _team = self.team.clone();
-        let url = format!("/orgs/navikt/teams/{}", &team_name);
-        let mut client = self.client.clone();
-        self.client.get_path(token, &url, move |response| {
-            if let Ok(response) = response {
-                if let Some(remaining) = response.headers.get("x-ratelimit-remaining") {
-                    let remaining = remaining.parse::<usize>().unwrap();
-                    client.set_rate_limit(remaining);
-                }
-
-                let team = serde_json::from_slice::<Team>(&response.bytes).unwrap_or_default();
-                *_team.lock().unwrap() = Some(team);
-            }
-        });
+        let url = format!("/orgs/{}/teams/{}", self.client.settings().org, &team_name);
+        self.client.get_json_path::<Team>(token, &url, self.fetches.track(TEAM, move |team| {
+            *_team.lock().unwrap() = Some(team);
+        }));
     }
 
     fn fetch_repositories(&mut self, token: &str) {
@@ -153,35 +167,60 @@ impl RepositoriesPanel {
         let _team = self.team.lock().unwrap().clone().unwrap(); // button is only visible if this is Some
         let _blacklisted = self.blacklisted.lock().unwrap().clone();
         let _archived = self.archived.clone();
-        let url = format!("{}{}", &_team.repositories_url, "?per_page=100");
-        let mut client = self.client.clone();
-        self.client.get(token, &url, move |response| {
-            if let Ok(response) = response {
-                
-                if let Some(remaining) = response.headers.get("x-ratelimit-remaining") {
-                    let remaining = remaining.parse::<usize>().unwrap();
-                    client.set_rate_limit(remaining);
-                }
-
-                let repositories = serde_json::from_slice::<Vec<Repository>>(&response.bytes).unwrap_or_default();
-                let repos = repositories
-                    .clone()
-                    .into_iter()
-                    .filter(|repo| !_blacklisted.contains(repo))
-                    .filter(|repo| !repo.archived)
-                    .collect_vec();
-
-                *_repositories.lock().unwrap() = repos;
-
-                let archived = repositories
-                    .clone()
-                    .into_iter()
-                    .filter(|repo| !_blacklisted.contains(repo))
-                    .filter(|repo| repo.archived)
-                    .collect_vec();
-
-                *_archived.lock().unwrap() = archived;
-            }
-        });
+        self.client.get_all::<Repository>(token, &_team.repositories_url, Limit::default(), self.fetches.track(REPOSITORIES, move |repositories| {
+            let (archived, repos) = split_archived(repositories, &_blacklisted);
+            *_repositories.lock().unwrap() = repos;
+            *_archived.lock().unwrap() = archived;
+        }));
+    }
+}
+
+#[cfg(test)]
+mod fetch_repositories {
+    use std::sync::Arc;
+
+    use http::github::Client;
+    use http::transport::FakeTransport;
+    use model::repository::Repository;
+    use model::team::Team;
+
+    use crate::panel::Panel;
+    use crate::panel_repository::RepositoriesPanel;
+
+    fn repository(id: i64, name: &str, archived: bool) -> String {
+        format!(r#"{{"id":{id},"name":"{name}","full_name":"navikt/{name}","html_url":"https://github.com/navikt/{name}","deployments_url":"","releases_url":"","pulls_url":"","description":null,"archived":{archived}}}"#)
+    }
+
+    fn panel(blacklisted: &[&str]) -> RepositoriesPanel {
+        let body = format!("[{},{},{}]", repository(1, "api", false), repository(2, "old", true), repository(3, "docs", false));
+        let transport = FakeTransport::default().respond("https://api.github.com/teams/1/repos*", 200, &body);
+
+        let mut panel = RepositoriesPanel::default();
+        panel.set_client(Client::default().with_transport(Arc::new(transport)));
+        *panel.team.lock().unwrap() = Some(Team { repositories_url: "https://api.github.com/teams/1/repos".to_string(), ..Team::default() });
+        let repositories = serde_json::from_str::<Vec<Repository>>(&body).unwrap();
+        *panel.blacklisted.lock().unwrap() = repositories.into_iter().filter(|repo| blacklisted.contains(&repo.name.as_str())).collect();
+        panel
+    }
+
+    fn names(repositories: Vec<Repository>) -> Vec<String> {
+        repositories.into_iter().map(|repo| repo.name).collect()
+    }
+
+    #[test]
+    fn archived_are_split_out() {
+        let mut panel = panel(&[]);
+        panel.fetch_repositories("token");
+        assert_eq!(names(panel.repositories()), vec!["api", "docs"]);
+        assert_eq!(names(panel.archived.lock().unwrap().clone()), vec!["old"]);
+    }
+
+    #[test]
+    fn blacklisted_are_left_out() {
+        let mut panel = panel(&["docs", "old"]);
+        panel.fetch_repositories("token");
+        assert_eq!(names(panel.repositories()), vec!["api"]);
+        assert!(panel.archived.lock().unwrap().is_empty());
+        assert_eq!(names(panel.blacklisted_repositories()), vec!["old", "docs"]);
     }
 }
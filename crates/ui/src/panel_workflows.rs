@@ -1,15 +1,27 @@
 use std::collections::BTreeMap;
 use std::sync::{Arc, Mutex};
 
-use egui::{Color32, SelectableLabel, Ui};
+use chrono::Duration;
+use egui::{Color32, RichText, SelectableLabel, Ui, Window};
 use egui_extras::TableBuilder;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 use http::github;
+use http::pagination::Limit;
 use model::repository::Repository;
-use model::workflow::{Workflow, WorkflowRun, WorkflowRuns};
+use model::workflow::{Conclusion, Job, RunStatus, Step, Workflow, WorkflowRun};
 
 use crate::{FixedField, Scroll, Scrollbar, Table};
-use crate::panel::Panel;
+use crate::auto_refresh::{AutoRefresh, AutoRefreshing};
+use crate::fetch_state::Fetches;
+use crate::log_viewer::LogViewer;
+use crate::panel::{Panel, SHARED_MAX_AGE_SECONDS};
+use crate::timestamp;
+use crate::workflow_dispatch::DispatchForm;
+use crate::workflow_jobs::JobsView;
+
+/// Enough recent runs to find the newest run of every workflow in a repository.
+const RUNS_PER_REPOSITORY: usize = 100;
 
 #[derive(Deserialize, Serialize, Default)]
 pub struct WorkflowPanel {
@@ -18,7 +30,67 @@ pub struct WorkflowPanel {
     workflow_runs: Arc<Mutex<BTreeMap<String, Vec<WorkflowRun>>>>,
     show_pull_requests: bool,
     show_successfuls: bool,
+    #[serde(skip)]
     client: github::Client,
+    /// Action waiting for the user to confirm it.
+    #[serde(skip)]
+    confirm: Option<Confirm>,
+    /// Outcome of the latest action per run id.
+    #[serde(skip)]
+    action_results: Arc<Mutex<BTreeMap<i64, String>>>,
+    #[serde(skip)]
+    dispatch: Option<DispatchForm>,
+    /// Jobs of the run whose row was clicked.
+    #[serde(skip)]
+    jobs: Option<JobsView>,
+    #[serde(skip)]
+    logs: Option<LogViewer>,
+    /// Runs per repository, and the workflow listing per repository.
+    #[serde(skip)]
+    fetches: Fetches,
+    #[serde(default)]
+    auto_refresh: AutoRefresh,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum RunAction {
+    Rerun,
+    RerunFailedJobs,
+    Cancel,
+}
+
+impl RunAction {
+    /// What can be done with a run, cancelling while it runs and re-running once it has concluded.
+    fn available(run: &WorkflowRun) -> Vec<RunAction> {
+        match run.conclusion {
+            None => vec![RunAction::Cancel],
+            Some(Conclusion::Success) | Some(Conclusion::Skipped) => vec![RunAction::Rerun],
+            Some(_) => vec![RunAction::Rerun, RunAction::RerunFailedJobs],
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            RunAction::Rerun => "Re-run",
+            RunAction::RerunFailedJobs => "Re-run failed jobs",
+            RunAction::Cancel => "Cancel",
+        }
+    }
+
+    fn url(&self, run: &WorkflowRun) -> String {
+        match self {
+            RunAction::Rerun => run.rerun_url.clone(),
+            RunAction::RerunFailedJobs => run.rerun_failed_jobs_url(),
+            RunAction::Cancel => run.cancel_url.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Confirm {
+    action: RunAction,
+    repository: Repository,
+    run: WorkflowRun,
 }
 
 impl Panel for WorkflowPanel {
@@ -40,74 +112,393 @@ impl Panel for WorkflowPanel {
             if ui.add(SelectableLabel::new(self.show_successfuls, "Show successful")).clicked() {
                 self.show_successfuls = !self.show_successfuls;
             };
+
+            self.auto_refresh.paint(ui);
+            self.fetches.paint_summary(ui);
+        });
+
+        ui.collapsing("Run workflow", |ui| {
+            let workflows = self.workflows.lock().unwrap().clone();
+            for (repo_name, workflows) in workflows.iter() {
+                ui.horizontal_wrapped(|ui| {
+                    ui.label(repo_name);
+                    self.fetches.paint_badge(ui, &workflows_key(repo_name));
+                    workflows.iter().filter(|workflow| workflow.state == "active").for_each(|workflow| {
+                        if ui.small_button(&workflow.name).on_hover_text(&workflow.path).clicked() {
+                            if let Some(repository) = self.repositories.iter().find(|repo| &repo.name == repo_name).cloned() {
+                                self.dispatch = Some(DispatchForm::open(&mut self.client, token, repository, workflow.clone()));
+                            }
+                        }
+                    });
+                });
+            }
         });
 
         FixedField::minimum_width(100.0, ui, |ui| {
             Scrollbar::horizontal(ui, |ui| {
                 let workflows = self.workflow_runs.lock().unwrap().clone();
+                let action_results = self.action_results.lock().unwrap().clone();
+                let mut requested = None;
+                let mut clicked = None;
+                let mut show_logs = None;
 
-                TableBuilder::create(ui, vec!["Repo", "Conclusion", "Workflow", "Event", "Attempts", "Timestamp"]).body(|mut body| {
+                TableBuilder::create(ui, vec!["Repo", "Conclusion", "Workflow", "Event", "Attempts", "Timestamp", "Actions"]).body(|mut body| {
                     for (repo_name, runs) in workflows.iter() {
-                        let newest_workflow_runs = runs.iter()
-                            .fold(BTreeMap::new(), |mut acc: BTreeMap<i64, WorkflowRun>, next| {
-                                let existing_or_new = acc.entry(next.workflow_id).or_default();
-                                if next.id > existing_or_new.id {
-                                    acc.insert(next.workflow_id, next.clone());
-                                }
-                                acc
-                            })
-                            .into_values();
-
-                        newest_workflow_runs
+                        newest_runs(runs)
                             .filter(|workflow_run| workflow_run.event.clone() != "pull_request" || self.show_pull_requests)
-                            .filter(|workflow_run| workflow_run.conclusion.clone().unwrap_or_default() != "success" || self.show_successfuls)
+                            .filter(|workflow_run| workflow_run.conclusion != Some(Conclusion::Success) || self.show_successfuls)
                             .for_each(|workflow_run| {
                                 body.row(18.0, |mut row| {
-                                    row.col(|ui| { ui.label(repo_name); });
                                     row.col(|ui| {
-                                        let color = match &workflow_run.conclusion {
-                                            Some(conclusion) if conclusion == "success" => Color32::LIGHT_GREEN,
-                                            Some(conclusion) if conclusion == "failure" => Color32::LIGHT_RED,
-                                            _ => Color32::LIGHT_GRAY
-                                        };
-
-                                        ui.colored_label(color, workflow_run.conclusion.unwrap_or_default());
+                                        ui.label(repo_name);
+                                        self.fetches.paint_badge(ui, repo_name);
+                                    });
+                                    row.col(|ui| {
+                                        let (color, icon) = workflow_run.appearance();
+                                        let text = RichText::new(format!("{icon} {}", workflow_run.state())).color(color);
+                                        let selected = matches!(&self.jobs, Some(jobs) if jobs.run_id() == workflow_run.id);
+                                        if ui.add(SelectableLabel::new(selected, text)).on_hover_text("Show jobs and steps").clicked() {
+                                            clicked = Some((repo_name.clone(), workflow_run.clone()));
+                                        }
                                     });
-                                    row.col(|ui| { ui.hyperlink_to(&workflow_run.name.clone().unwrap_or_default(), &workflow_run.html_url.clone()); });
-                                    row.col(|ui| { ui.label(&workflow_run.event.clone()); });
+                                    row.col(|ui| { ui.hyperlink_to(workflow_run.name.clone().unwrap_or_default(), workflow_run.html_url.clone()); });
+                                    row.col(|ui| { ui.label(workflow_run.event.clone()); });
                                     row.col(|ui| { ui.label(format!("{}", &workflow_run.run_attempt.clone())); });
-                                    row.col(|ui| { ui.label(&workflow_run.run_started_at.clone().unwrap_or_default()); });
+                                    row.col(|ui| { timestamp::paint_optional(ui, workflow_run.run_started_at); });
+                                    row.col(|ui| {
+                                        RunAction::available(&workflow_run).into_iter().for_each(|action| {
+                                            if ui.small_button(action.label()).clicked() {
+                                                requested = Some((action, repo_name.clone(), workflow_run.clone()));
+                                            }
+                                        });
+                                        if workflow_run.conclusion.is_some() && ui.small_button("Logs").clicked() {
+                                            show_logs = Some((repo_name.clone(), workflow_run.clone()));
+                                        }
+                                        if let Some(result) = action_results.get(&workflow_run.id) {
+                                            ui.label(result);
+                                        }
+                                    });
                                 });
                             });
                     }
+
+                    self.fetches.failures().into_iter()
+                        .filter(|(key, _)| !workflows.contains_key(key) && self.repositories.iter().any(|repo| &repo.name == key))
+                        .for_each(|(repo_name, reason)| {
+                            body.row(18.0, |mut row| {
+                                row.col(|ui| {
+                                    ui.label(&repo_name);
+                                    self.fetches.paint_badge(ui, &repo_name);
+                                });
+                                row.col(|ui| { ui.colored_label(Color32::LIGHT_RED, reason); });
+                            });
+                        });
                 });
+
+                if let Some((action, repo_name, run)) = requested {
+                    let repository = self.repositories.iter().find(|repo| repo.name == repo_name).cloned();
+                    self.confirm = repository.map(|repository| Confirm { action, repository, run });
+                }
+
+                if let Some((repo_name, run)) = clicked {
+                    self.jobs = match &self.jobs {
+                        Some(jobs) if jobs.run_id() == run.id => None,
+                        _ => Some(JobsView::open(&mut self.client, token, repo_name, run)),
+                    };
+                }
+
+                if let Some((repo_name, run)) = show_logs {
+                    self.logs = Some(LogViewer::open(&mut self.client, token, &repo_name, &run));
+                }
             });
         });
+
+        self.paint_confirm(ui, token);
+        self.paint_dispatch(ui, token);
+        if let Some(jobs) = &mut self.jobs {
+            if !jobs.paint(ui) {
+                self.jobs = None;
+            }
+        }
+        if let Some(logs) = &mut self.logs {
+            if !logs.paint(ui) {
+                self.logs = None;
+            }
+        }
+    }
+}
+
+impl AutoRefreshing for WorkflowPanel {
+    fn auto_refresh(&mut self) -> &mut AutoRefresh { &mut self.auto_refresh }
+    fn refresh_now(&mut self, token: &str) { self.refresh(token) }
+}
+
+/// How a run, job or step is shown, from its conclusion and status.
+pub(crate) trait RunAppearance {
+    fn conclusion_and_status(&self) -> (&Option<Conclusion>, &Option<RunStatus>);
+
+    /// Colour and icon of the conclusion, or of the status while there is none.
+    fn appearance(&self) -> (Color32, &'static str) {
+        match self.conclusion_and_status() {
+            (Some(Conclusion::Success), _) => (Color32::LIGHT_GREEN, "✔"),
+            (Some(Conclusion::Failure), _) => (Color32::LIGHT_RED, "✖"),
+            (Some(Conclusion::TimedOut), _) => (Color32::LIGHT_RED, "⏱"),
+            (Some(Conclusion::StartupFailure), _) => (Color32::LIGHT_RED, "⚠"),
+            (Some(Conclusion::ActionRequired), _) => (Color32::YELLOW, "✋"),
+            (Some(Conclusion::Cancelled), _) => (Color32::GRAY, "🚫"),
+            (Some(Conclusion::Skipped), _) => (Color32::LIGHT_GRAY, "⏭"),
+            (Some(Conclusion::Neutral), _) | (Some(Conclusion::Stale), _) => (Color32::LIGHT_GRAY, "➖"),
+            (Some(Conclusion::Unknown(_)), _) => (Color32::LIGHT_GRAY, "?"),
+            (None, Some(RunStatus::InProgress)) => (Color32::LIGHT_BLUE, "🔄"),
+            (None, Some(RunStatus::Waiting)) => (Color32::YELLOW, "⏸"),
+            (None, Some(RunStatus::Queued)) | (None, Some(RunStatus::Requested)) | (None, Some(RunStatus::Pending)) => (Color32::LIGHT_BLUE, "⏳"),
+            (None, _) => (Color32::LIGHT_GRAY, "?"),
+        }
+    }
+
+    /// The conclusion, or the status while there is none.
+    fn state(&self) -> String {
+        match self.conclusion_and_status() {
+            (Some(conclusion), _) => conclusion.to_string(),
+            (None, Some(status)) => status.to_string(),
+            (None, None) => String::default(),
+        }
     }
 }
 
+impl RunAppearance for WorkflowRun {
+    fn conclusion_and_status(&self) -> (&Option<Conclusion>, &Option<RunStatus>) { (&self.conclusion, &self.status) }
+}
+
+impl RunAppearance for Job {
+    fn conclusion_and_status(&self) -> (&Option<Conclusion>, &Option<RunStatus>) { (&self.conclusion, &self.status) }
+}
+
+impl RunAppearance for Step {
+    fn conclusion_and_status(&self) -> (&Option<Conclusion>, &Option<RunStatus>) { (&self.conclusion, &self.status) }
+}
+
+/// The run with the highest id for each workflow.
+fn newest_runs(runs: &[WorkflowRun]) -> impl Iterator<Item = WorkflowRun> {
+    runs.iter()
+        .fold(BTreeMap::new(), |mut acc: BTreeMap<i64, WorkflowRun>, next| {
+            let existing_or_new = acc.entry(next.workflow_id).or_default();
+            if next.id > existing_or_new.id {
+                acc.insert(next.workflow_id, next.clone());
+            }
+            acc
+        })
+        .into_values()
+}
+
 impl WorkflowPanel {
     fn refresh(&mut self, token: &str) {
         self.workflow_runs.lock().unwrap().clear();
-        self.repositories.clone().into_iter().for_each(|_repo| {
-            let _workflow_runs = self.workflow_runs.clone();
-            let url = format!("/repos/navikt/{}/actions/runs?per_page=15", _repo.name);
-            let mut client = self.client.clone();
-            self.client.get_path(token, &url, move |response| {
-                if let Ok(response) = response {
-
-                    if let Some(remaining) = response.headers.get("x-ratelimit-remaining") {
-                        let remaining = remaining.parse::<usize>().unwrap();
-                        client.set_rate_limit(remaining);
-                    }
+        self.workflows.lock().unwrap().clear();
+        self.fetches.clear();
+        self.repositories.clone().into_iter().for_each(|repo| {
+            refresh_repository(&mut self.client, token, &repo, self.workflow_runs.clone(), &self.fetches);
 
-                    let workflow_runs = serde_json::from_slice::<WorkflowRuns>(&response.bytes).unwrap_or_default();
+            // workflow definitions rarely change, so a recent listing will do
+            let workflows = self.workflows.clone();
+            let url = format!("/repos/{}/actions/workflows", repo.full_name);
+            let on_listed = self.fetches.track(&workflows_key(&repo.name), move |response| {
+                workflows.lock().unwrap().insert(repo.name, response);
+            });
+            self.client.with_max_age(Duration::seconds(SHARED_MAX_AGE_SECONDS)).get_all_path::<Workflow>(token, &url, Limit::default(), on_listed);
+        });
+    }
+
+    fn paint_dispatch(&mut self, ui: &mut Ui, token: &str) {
+        let Some(mut dispatch) = self.dispatch.take() else {
+            return;
+        };
 
-                    *_workflow_runs.lock().unwrap()
-                        .entry(_repo.clone().name)
-                        .or_default() = workflow_runs.workflow_runs;
+        let mut client = self.client.clone();
+        let workflow_runs = self.workflow_runs.clone();
+        let repository = dispatch.repository().clone();
+        let token_owned = token.to_string();
+        let fetches = self.fetches.clone();
+        let open = dispatch.paint(ui, &mut self.client, token, move || {
+            refresh_repository(&mut client, &token_owned, &repository, workflow_runs, &fetches);
+        });
+
+        if open {
+            self.dispatch = Some(dispatch);
+        }
+    }
+
+    fn paint_confirm(&mut self, ui: &mut Ui, token: &str) {
+        let Some(confirm) = self.confirm.clone() else {
+            return;
+        };
+
+        let mut answer = None;
+        Window::new("Confirm").collapsible(false).resizable(false).show(ui.ctx(), |ui| {
+            ui.label(format!(
+                "{} \"{}\" #{} in {}?",
+                confirm.action.label(),
+                confirm.run.name.clone().unwrap_or_default(),
+                confirm.run.id,
+                confirm.repository.name,
+            ));
+            ui.horizontal(|ui| {
+                if ui.button("Yes").clicked() {
+                    answer = Some(true);
+                }
+                if ui.button("No").clicked() {
+                    answer = Some(false);
                 }
             });
         });
+
+        if let Some(answer) = answer {
+            self.confirm = None;
+            if answer {
+                self.run_action(token, confirm);
+            }
+        }
+    }
+
+    /// Posts the action, keeps its outcome for the run and then refreshes the runs of its repository.
+    fn run_action(&mut self, token: &str, confirm: Confirm) {
+        let Confirm { action, repository, run } = confirm;
+        self.action_results.lock().unwrap().insert(run.id, format!("{}...", action.label()));
+
+        let action_results = self.action_results.clone();
+        let workflow_runs = self.workflow_runs.clone();
+        let mut client = self.client.clone();
+        let token_owned = token.to_string();
+        let fetches = self.fetches.clone();
+        self.client.post(token, &action.url(&run), &json!({}), move |response| {
+            let result = match response {
+                Ok(_) => format!("{} requested", action.label()),
+                Err(e) => format!("{} failed: {e}", action.label()),
+            };
+            action_results.lock().unwrap().insert(run.id, result);
+            refresh_repository(&mut client, &token_owned, &repository, workflow_runs, &fetches);
+        });
+    }
+}
+
+fn refresh_repository(
+    client: &mut github::Client,
+    token: &str,
+    repo: &Repository,
+    workflow_runs: Arc<Mutex<BTreeMap<String, Vec<WorkflowRun>>>>,
+    fetches: &Fetches,
+) {
+    let url = format!("/repos/{}/actions/runs", repo.full_name);
+    let repo_name = repo.name.clone();
+    client.get_all_path::<WorkflowRun>(token, &url, Limit::items(RUNS_PER_REPOSITORY), fetches.track(&repo.name, move |runs| {
+        *workflow_runs.lock().unwrap()
+            .entry(repo_name)
+            .or_default() = runs;
+    }));
+}
+
+/// Fetch state key of a repository's workflow listing.
+fn workflows_key(repo_name: &str) -> String {
+    format!("{repo_name} workflows")
+}
+
+#[cfg(test)]
+mod refresh {
+    use std::sync::Arc;
+
+    use http::github::Client;
+    use http::transport::FakeTransport;
+    use model::repository::Repository;
+
+    use crate::fetch_state::FetchState;
+    use crate::panel::Panel;
+    use crate::panel_workflows::{newest_runs, Confirm, RunAction, RunAppearance, WorkflowPanel};
+
+    fn run(id: i64, workflow_id: i64, conclusion: &str) -> String {
+        format!(r#"{{"id":{id},"name":"build","run_attempt":1,"event":"push","conclusion":"{conclusion}","workflow_id":{workflow_id},"html_url":"","run_started_at":"2023-02-01T10:00:00Z","actor":null,"triggering_actor":null,"jobs_url":"","logs_url":"","check_suite_url":"","artifacts_url":"","cancel_url":"https://api.github.com/repos/navikt/api/actions/runs/{id}/cancel","rerun_url":"https://api.github.com/repos/navikt/api/actions/runs/{id}/rerun","workflow_url":"","display_title":"build"}}"#)
+    }
+
+    fn repository() -> Repository {
+        let repository = r#"{"id":1,"name":"api","full_name":"navikt/api","html_url":"","deployments_url":"","releases_url":"","pulls_url":"","description":null,"archived":false}"#;
+        serde_json::from_str::<Repository>(repository).unwrap()
+    }
+
+    fn panel_with(transport: Arc<FakeTransport>) -> WorkflowPanel {
+        let mut panel = WorkflowPanel::default();
+        panel.set_client(Client::default().with_transport(transport));
+        panel.set_repositories(vec![repository()]);
+        panel
+    }
+
+    fn runs() -> String {
+        format!(r#"{{"total_count":4,"workflow_runs":[{},{},{},{}]}}"#, run(14, 1, "failure"), run(13, 2, "success"), run(12, 1, "success"), run(11, 2, "failure"))
+    }
+
+    fn panel() -> WorkflowPanel {
+        panel_with(Arc::new(FakeTransport::default()
+            .respond("https://api.github.com/repos/navikt/api/actions/runs*", 200, &runs())
+            .respond("https://api.github.com/repos/navikt/api/actions/workflows*", 200, r#"{"total_count":1,"workflows":[{"id":9,"node_id":"","name":"Deploy","path":".github/workflows/deploy.yml","state":"active"}]}"#)))
+    }
+
+    #[test]
+    fn newest_run_per_workflow() {
+        let mut panel = panel();
+        panel.refresh("token");
+        let runs = panel.workflow_runs.lock().unwrap().get("api").unwrap().clone();
+        assert_eq!(runs.len(), 4);
+        assert_eq!(panel.workflows.lock().unwrap().get("api").unwrap()[0].name, "Deploy");
+
+        let newest = newest_runs(&runs).map(|run| (run.workflow_id, run.id)).collect::<Vec<_>>();
+        assert_eq!(newest, vec![(1, 14), (2, 13)]);
+    }
+
+    #[test]
+    fn every_failure_is_red() {
+        ["failure", "timed_out", "startup_failure"].into_iter().for_each(|conclusion| {
+            let run = serde_json::from_str::<model::workflow::WorkflowRun>(&run(1, 1, conclusion)).unwrap();
+            assert_eq!(run.appearance().0, egui::Color32::LIGHT_RED, "{conclusion}");
+        });
+        let cancelled = serde_json::from_str::<model::workflow::WorkflowRun>(&run(1, 1, "cancelled")).unwrap();
+        assert_eq!(cancelled.appearance(), (egui::Color32::GRAY, "🚫"));
+        assert_eq!(cancelled.state(), "cancelled");
+    }
+
+    #[test]
+    fn failed_repository() {
+        let mut panel = panel_with(Arc::new(FakeTransport::default()
+            .respond("https://api.github.com/repos/navikt/api/actions/runs*", 404, r#"{"message":"Not Found"}"#)
+            .respond("https://api.github.com/repos/navikt/api/actions/workflows*", 200, r#"{"total_count":0,"workflows":[]}"#)));
+        panel.refresh("token");
+
+        assert_eq!(panel.fetches.get("api"), FetchState::Failed("404: Not Found".to_string()));
+        assert!(matches!(panel.fetches.get("api workflows"), FetchState::Loaded(_)));
+    }
+
+    #[test]
+    fn rerun_failed_jobs_then_refresh() {
+        let transport = Arc::new(FakeTransport::default()
+            .respond("https://api.github.com/repos/navikt/api/actions/runs/14/rerun-failed-jobs", 201, "")
+            .respond("https://api.github.com/repos/navikt/api/actions/runs*", 200, &runs()));
+        let mut panel = panel_with(transport.clone());
+        let run = serde_json::from_str(&run(14, 1, "failure")).unwrap();
+        panel.run_action("token", Confirm { action: RunAction::RerunFailedJobs, repository: repository(), run });
+
+        assert_eq!(transport.requests(), vec![
+            "https://api.github.com/repos/navikt/api/actions/runs/14/rerun-failed-jobs",
+            "https://api.github.com/repos/navikt/api/actions/runs?per_page=100",
+        ]);
+        assert_eq!(panel.action_results.lock().unwrap().get(&14).unwrap(), "Re-run failed jobs requested");
+        assert_eq!(panel.workflow_runs.lock().unwrap().get("api").unwrap().len(), 4);
+    }
+
+    #[test]
+    fn failed_action() {
+        let transport = Arc::new(FakeTransport::default()
+            .respond("*/cancel", 409, r#"{"message":"Cannot cancel a workflow run that is completed."}"#));
+        let mut panel = panel_with(transport);
+        let run = serde_json::from_str(&run(13, 2, "success")).unwrap();
+        panel.run_action("token", Confirm { action: RunAction::Cancel, repository: repository(), run });
+
+        assert_eq!(panel.action_results.lock().unwrap().get(&13).unwrap(), "Cancel failed: 409: Cannot cancel a workflow run that is completed.");
     }
 }
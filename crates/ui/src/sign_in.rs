@@ -0,0 +1,138 @@
+use chrono::Utc;
+use egui::{Color32, Ui};
+
+use http::device_flow::{DeviceFlow, FlowState, Session};
+use http::github::Client;
+
+/// Scopes asked for when signing in, the same a personal token needs.
+const SCOPES: &str = "repo read:org";
+
+/// "Sign in with GitHub" through the device flow, as an alternative to pasting a token.
+/// The session is stored encrypted along with the token, see [crate::vault::Vault].
+#[derive(Default)]
+pub struct SignIn {
+    /// Kept to refresh the token with, for as long as it is the token in use.
+    session: Option<Session>,
+    flow: DeviceFlow,
+}
+
+impl SignIn {
+    pub fn session(&self) -> Option<Session> {
+        self.session.clone()
+    }
+
+    /// Picks up a session unlocked from storage.
+    pub fn restore(&mut self, session: Option<Session>) {
+        self.session = session;
+    }
+
+    /// Drops the session and stops any sign in that is going on.
+    pub fn forget(&mut self) {
+        self.session = None;
+        self.flow.cancel();
+    }
+
+    /// Puts a new or refreshed token where a pasted one would go, and refreshes it before it expires.
+    pub fn update(&mut self, client: &Client, token: &mut String) {
+        if let Some(session) = self.flow.take_session() {
+            *token = session.access_token.clone();
+            self.session = Some(session);
+        }
+
+        // a token pasted over the signed in one ends the session
+        if matches!(&self.session, Some(session) if session.access_token != *token) {
+            self.session = None;
+        }
+
+        if let Some(refresh_token) = self.session.as_ref()
+            .filter(|session| session.needs_refresh(Utc::now()))
+            .and_then(|session| session.refresh_token.clone()) {
+            if self.flow.state() == FlowState::Idle {
+                self.flow.refresh(client, &refresh_token);
+            }
+        }
+    }
+
+    pub fn paint(&mut self, ui: &mut Ui, client: &Client) {
+        match self.flow.state() {
+            FlowState::Requesting => {
+                ui.spinner();
+                ui.ctx().request_repaint_after(std::time::Duration::from_secs(1));
+            }
+            FlowState::Waiting { user_code, verification_uri, expires_at } => {
+                ui.label("Enter");
+                ui.strong(&user_code);
+                if ui.small_button("📋").on_hover_text("Copy code").clicked() {
+                    ui.output_mut(|output| output.copied_text = user_code.clone());
+                }
+                ui.label("at");
+                ui.hyperlink(&verification_uri);
+                ui.label(format!("within {} minutes", (expires_at - Utc::now()).num_minutes() + 1));
+                if ui.button("Cancel").clicked() {
+                    self.flow.cancel();
+                }
+                ui.ctx().request_repaint_after(std::time::Duration::from_secs(1));
+            }
+            state => {
+                if let FlowState::Failed(e) = state {
+                    ui.colored_label(Color32::LIGHT_RED, e);
+                }
+                let configured = !client.settings().oauth_client_id.trim().is_empty();
+                let button = ui.add_enabled(configured, egui::Button::new("Sign in with GitHub"))
+                    .on_disabled_hover_text("Needs the client id of an OAuth App with device flow enabled, see Settings");
+                if button.clicked() {
+                    self.flow.start(client, SCOPES);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod session {
+    use std::sync::Arc;
+
+    use http::github::Client;
+    use http::transport::FakeTransport;
+
+    use crate::sign_in::SignIn;
+
+    fn client(transport: FakeTransport) -> Client {
+        let mut client = Client::default().with_transport(Arc::new(transport));
+        client.settings_mut().oauth_client_id = "Iv1.client".to_string();
+        client
+    }
+
+    #[test]
+    fn signed_in_token_replaces_pasted_one_and_is_refreshed() {
+        let client = client(FakeTransport::default()
+            .respond("https://github.com/login/device/code", 200, r#"{"device_code":"device","user_code":"WDJB-MJHT","verification_uri":"https://github.com/login/device","expires_in":900,"interval":5}"#)
+            .respond("https://github.com/login/oauth/access_token", 200, r#"{"access_token":"ghu_first","expires_in":60,"refresh_token":"ghr_first"}"#)
+            .respond("https://github.com/login/oauth/access_token", 200, r#"{"access_token":"ghu_second","expires_in":28800,"refresh_token":"ghr_second"}"#));
+        let mut sign_in = SignIn::default();
+        let mut token = "ghp_pasted".to_string();
+
+        sign_in.flow.start(&client, "repo");
+        sign_in.update(&client, &mut token);
+        // expires within the refresh margin, so the refreshed token is in place by the next frame
+        assert_eq!(token, "ghu_first");
+        sign_in.update(&client, &mut token);
+        assert_eq!(token, "ghu_second");
+    }
+
+    #[test]
+    fn pasting_a_token_ends_the_session() {
+        let client = client(FakeTransport::default()
+            .respond("https://github.com/login/device/code", 200, r#"{"device_code":"device","user_code":"WDJB-MJHT","verification_uri":"https://github.com/login/device","expires_in":900,"interval":5}"#)
+            .respond("https://github.com/login/oauth/access_token", 200, r#"{"access_token":"ghu_first","expires_in":28800,"refresh_token":"ghr_first"}"#));
+        let mut sign_in = SignIn::default();
+        let mut token = String::default();
+
+        sign_in.flow.start(&client, "repo");
+        sign_in.update(&client, &mut token);
+        token = "ghp_pasted".to_string();
+        sign_in.update(&client, &mut token);
+        assert_eq!(sign_in.session, None);
+        assert_eq!(token, "ghp_pasted");
+    }
+}
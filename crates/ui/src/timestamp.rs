@@ -0,0 +1,80 @@
+use chrono::{DateTime, Duration, Utc};
+use chrono_tz::Tz;
+use egui::{Response, Ui};
+
+/// The team sits in Oslo, so times are shown there whatever zone the machine is set to.
+pub const ZONE: Tz = chrono_tz::Europe::Oslo;
+
+/// "3 h ago", coarse enough to take in at a glance. Older than a month is shown as the date.
+pub fn relative(at: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    match now - at {
+        elapsed if elapsed < Duration::minutes(1) => "just now".to_string(),
+        elapsed if elapsed < Duration::hours(1) => format!("{} min ago", elapsed.num_minutes()),
+        elapsed if elapsed < Duration::days(1) => format!("{} h ago", elapsed.num_hours()),
+        elapsed if elapsed < Duration::days(30) => format!("{} d ago", elapsed.num_days()),
+        _ => at.with_timezone(&ZONE).format("%Y-%m-%d").to_string(),
+    }
+}
+
+/// Date and time in Oslo, with the zone so it is clear whether summer time applies.
+pub fn absolute(at: DateTime<Utc>) -> String {
+    at.with_timezone(&ZONE).format("%Y-%m-%d %H:%M:%S %Z").to_string()
+}
+
+/// Time of day in Oslo, for what happened in this session.
+pub fn clock(at: DateTime<Utc>, format: &str) -> String {
+    at.with_timezone(&ZONE).format(format).to_string()
+}
+
+/// How long something took, to the second: "45 s", "3 min 12 s", "1 h 5 min".
+pub fn duration(duration: Duration) -> String {
+    let seconds = duration.num_seconds().max(0);
+    match seconds {
+        seconds if seconds < 60 => format!("{seconds} s"),
+        seconds if seconds < 3600 => format!("{} min {} s", seconds / 60, seconds % 60),
+        seconds => format!("{} h {} min", seconds / 3600, seconds % 3600 / 60),
+    }
+}
+
+/// Relative time, with the absolute time on hover.
+pub fn paint(ui: &mut Ui, at: DateTime<Utc>) -> Response {
+    ui.label(relative(at, Utc::now())).on_hover_text(absolute(at))
+}
+
+/// As [paint], or nothing when GitHub left the time out.
+pub fn paint_optional(ui: &mut Ui, at: Option<DateTime<Utc>>) {
+    if let Some(at) = at {
+        paint(ui, at);
+    }
+}
+
+#[cfg(test)]
+mod relative {
+    use chrono::{Duration, TimeZone, Utc};
+
+    use crate::timestamp::{absolute, duration, relative};
+
+    #[test]
+    fn coarse() {
+        let now = Utc.with_ymd_and_hms(2023, 3, 1, 12, 0, 0).unwrap();
+        assert_eq!(relative(now - Duration::seconds(20), now), "just now");
+        assert_eq!(relative(now + Duration::seconds(5), now), "just now", "clocks differ a little");
+        assert_eq!(relative(now - Duration::minutes(59), now), "59 min ago");
+        assert_eq!(relative(now - Duration::minutes(200), now), "3 h ago");
+        assert_eq!(relative(now - Duration::hours(50), now), "2 d ago");
+        assert_eq!(relative(now - Duration::days(45), now), "2023-01-15");
+    }
+
+    #[test]
+    fn oslo_with_summer_time() {
+        assert_eq!(absolute(Utc.with_ymd_and_hms(2023, 2, 1, 10, 0, 0).unwrap()), "2023-02-01 11:00:00 CET");
+        assert_eq!(absolute(Utc.with_ymd_and_hms(2023, 7, 1, 10, 0, 0).unwrap()), "2023-07-01 12:00:00 CEST");
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(duration(Duration::seconds(45)), "45 s");
+        assert_eq!(duration(Duration::seconds(192)), "3 min 12 s");
+        assert_eq!(duration(Duration::seconds(3900)), "1 h 5 min");
+    }
+}
@@ -0,0 +1,197 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use chrono::{Duration, Utc};
+use egui::{Color32, Ui};
+
+use http::error::GitHubError;
+use http::github::Client;
+use http::token::TokenInfo;
+use model::repository::Repository;
+
+use crate::timestamp;
+
+/// Scopes a classic token needs for each panel.
+const REQUIRED_SCOPES: [(&str, &[&str]); 4] = [
+    ("Repositories", &["read:org", "repo"]),
+    ("Pull Requests", &["repo"]),
+    ("Deployments", &["repo"]),
+    ("Workflows", &["repo"]),
+];
+
+/// What a fine-grained token is tried against for each panel,
+/// `{org}` is the configured organisation and `{repo}` any repository the token can see there.
+const PROBES: [(&str, &str); 4] = [
+    ("Repositories", "/orgs/{org}/teams?per_page=1"),
+    ("Pull Requests", "/repos/{repo}/pulls?per_page=1"),
+    ("Deployments", "/repos/{repo}/deployments?per_page=1"),
+    ("Workflows", "/repos/{repo}/actions/runs?per_page=1"),
+];
+
+/// Tokens about to expire get a warning.
+const EXPIRY_WARNING_DAYS: i64 = 7;
+
+/// Finds out who a token belongs to and which panels it will not work for.
+#[derive(Default)]
+pub struct TokenCheck {
+    checked: String,
+    info: Arc<Mutex<Option<Result<TokenInfo, GitHubError>>>>,
+    /// Why a fine-grained token failed, per panel.
+    probes: Arc<Mutex<BTreeMap<&'static str, String>>>,
+}
+
+impl TokenCheck {
+    /// The token the latest check was for.
+    pub fn checked(&self) -> &str {
+        &self.checked
+    }
+
+    /// Reads the scopes of a classic token, or tries what a fine-grained token may do.
+    pub fn check(&mut self, client: &mut Client, token: &str) {
+        self.checked = token.to_string();
+        *self.info.lock().unwrap() = None;
+        self.probes.lock().unwrap().clear();
+        if token.is_empty() {
+            return;
+        }
+
+        let info = self.info.clone();
+        let probes = self.probes.clone();
+        let mut probe_client = client.clone();
+        let token_owned = token.to_string();
+        client.token_info(token, move |response| {
+            if matches!(&response, Ok(TokenInfo { scopes: None, .. })) {
+                probe(&mut probe_client, &token_owned, probes);
+            }
+            *info.lock().unwrap() = Some(response);
+        });
+    }
+
+    /// Each panel that will fail, and why.
+    pub fn problems(&self) -> Vec<(&'static str, String)> {
+        let Some(Ok(info)) = self.info.lock().unwrap().clone() else {
+            return vec![];
+        };
+
+        if info.scopes.is_none() {
+            return self.probes.lock().unwrap().clone().into_iter().collect();
+        }
+
+        REQUIRED_SCOPES.iter()
+            .filter_map(|(panel, scopes)| {
+                let missing = scopes.iter().filter(|scope| info.has_scope(scope) == Some(false)).copied().collect::<Vec<_>>();
+                (!missing.is_empty()).then(|| (*panel, format!("missing the {} scope", missing.join(" and "))))
+            })
+            .collect()
+    }
+
+    pub fn paint(&self, ui: &mut Ui) {
+        if self.checked.is_empty() {
+            return;
+        }
+
+        match self.info.lock().unwrap().clone() {
+            None => {
+                ui.spinner();
+                ui.label("Checking token");
+            }
+            Some(Err(e)) => {
+                ui.colored_label(Color32::LIGHT_RED, format!("Token rejected: {e}"));
+            }
+            Some(Ok(info)) => {
+                ui.label(format!("Logged in as {}", info.login));
+                match info.expires_at {
+                    None => ui.label("Token never expires"),
+                    Some(expires_at) => {
+                        let color = match expires_at - Utc::now() < Duration::days(EXPIRY_WARNING_DAYS) {
+                            true => Color32::LIGHT_RED,
+                            false => ui.visuals().text_color(),
+                        };
+                        ui.colored_label(color, format!("Token expires {}", timestamp::absolute(expires_at)))
+                    }
+                };
+            }
+        }
+
+        self.problems().into_iter().for_each(|(panel, problem)| {
+            ui.colored_label(Color32::LIGHT_RED, format!("{panel} will fail: {problem}"));
+        });
+    }
+}
+
+/// Fine-grained tokens do not list their permissions, so try one request per panel.
+fn probe(client: &mut Client, token: &str, probes: Arc<Mutex<BTreeMap<&'static str, String>>>) {
+    let org = client.settings().org.clone();
+    let mut repo_client = client.clone();
+    let token_owned = token.to_string();
+    client.get_json_path::<Vec<Repository>>(token, &format!("/orgs/{org}/repos?per_page=1"), move |response| {
+        let repository = match response {
+            Ok(repositories) if !repositories.is_empty() => repositories[0].full_name.clone(),
+            Ok(_) => return probe_failed(&probes, "Repositories", format!("no repository in {org} is visible to the token")),
+            Err(e) => return probe_failed(&probes, "Repositories", no_access(&e)),
+        };
+
+        PROBES.iter().for_each(|(panel, path)| {
+            let probes = probes.clone();
+            let path = path.replace("{org}", &org).replace("{repo}", &repository);
+            repo_client.get_path(&token_owned, &path, move |response| {
+                let e = match response {
+                    Ok(response) if response.ok => return,
+                    Ok(response) => GitHubError::from_response(&response),
+                    Err(e) => e,
+                };
+                probe_failed(&probes, panel, no_access(&e));
+            });
+        });
+    });
+}
+
+fn probe_failed(probes: &Mutex<BTreeMap<&'static str, String>>, panel: &'static str, problem: String) {
+    probes.lock().unwrap().insert(panel, problem);
+}
+
+fn no_access(e: &GitHubError) -> String {
+    format!("no access ({e})")
+}
+
+#[cfg(test)]
+mod check {
+    use std::sync::Arc;
+
+    use http::github::Client;
+    use http::transport::{response, FakeTransport};
+
+    use crate::token_check::TokenCheck;
+
+    fn check(transport: FakeTransport) -> TokenCheck {
+        let mut client = Client::default().with_transport(Arc::new(transport));
+        let mut check = TokenCheck::default();
+        check.check(&mut client, "token");
+        check
+    }
+
+    #[test]
+    fn classic_without_repo_scope() {
+        let check = check(FakeTransport::default()
+            .respond_with("https://api.github.com/user", Ok(response(200, &[("x-oauth-scopes", "read:org")], r#"{"login":"octocat"}"#))));
+        assert_eq!(check.problems(), vec![
+            ("Repositories", "missing the repo scope".to_string()),
+            ("Pull Requests", "missing the repo scope".to_string()),
+            ("Deployments", "missing the repo scope".to_string()),
+            ("Workflows", "missing the repo scope".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn fine_grained_without_actions() {
+        let check = check(FakeTransport::default()
+            .respond("https://api.github.com/user", 200, r#"{"login":"octocat"}"#)
+            .respond("https://api.github.com/orgs/navikt/teams*", 200, "[]")
+            .respond("https://api.github.com/orgs/navikt/repos*", 200, r#"[{"id":1,"name":"api","full_name":"navikt/api","html_url":"","deployments_url":"","releases_url":"","pulls_url":"","description":null,"archived":false}]"#)
+            .respond("https://api.github.com/repos/navikt/api/actions/runs*", 403, r#"{"message":"Resource not accessible by personal access token"}"#)
+            .respond("https://api.github.com/repos/navikt/api/*", 200, "[]"));
+        assert_eq!(check.problems(), vec![
+            ("Workflows", "no access (403: Resource not accessible by personal access token)".to_string()),
+        ]);
+    }
+}
@@ -0,0 +1,255 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use chrono::Duration;
+use egui::{Button, Color32, ComboBox, Grid, Ui, Window};
+
+use http::github;
+use http::pagination::Limit;
+use model::content::Content;
+use model::environment::Environment;
+use model::repository::Repository;
+use model::workflow::Workflow;
+use model::workflow_dispatch::{dispatch_inputs, Dispatch, DispatchInput, InputKind};
+
+use crate::panel::SHARED_MAX_AGE_SECONDS;
+
+type Inputs = Result<Option<Vec<DispatchInput>>, String>;
+
+/// Form for running a `workflow_dispatch` workflow, with one typed field per input.
+pub struct DispatchForm {
+    repository: Repository,
+    workflow: Workflow,
+    git_ref: String,
+    values: BTreeMap<String, String>,
+    /// Parsed from the workflow file once it has been fetched.
+    inputs: Arc<Mutex<Option<Inputs>>>,
+    environments: Arc<Mutex<Vec<String>>>,
+    result: Arc<Mutex<Option<String>>>,
+}
+
+impl DispatchForm {
+    /// Starts fetching the workflow file and the environments an input may refer to,
+    /// which the Deployments panel may just have fetched.
+    pub fn open(client: &mut github::Client, token: &str, repository: Repository, workflow: Workflow) -> Self {
+        let mut client = client.with_max_age(Duration::seconds(SHARED_MAX_AGE_SECONDS));
+        let form = DispatchForm {
+            git_ref: match repository.default_branch.is_empty() {
+                true => "main".to_string(),
+                false => repository.default_branch.clone(),
+            },
+            repository,
+            workflow,
+            values: BTreeMap::new(),
+            inputs: Arc::default(),
+            environments: Arc::default(),
+            result: Arc::default(),
+        };
+
+        let inputs = form.inputs.clone();
+        let path = format!("/repos/{}/contents/{}", form.repository.full_name, form.workflow.path);
+        client.get_json_path::<Content>(token, &path, move |response| {
+            let parsed = response
+                .map_err(|e| e.to_string())
+                .and_then(|content| content.decoded())
+                .and_then(|yaml| dispatch_inputs(&yaml));
+            *inputs.lock().unwrap() = Some(parsed);
+        });
+
+        let environments = form.environments.clone();
+        let path = format!("/repos/{}/environments", form.repository.full_name);
+        client.get_all_path::<Environment>(token, &path, Limit::default(), move |response| {
+            if let Ok(response) = response {
+                *environments.lock().unwrap() = response.into_iter().map(|env| env.name).collect();
+            }
+        });
+
+        form
+    }
+
+    pub fn repository(&self) -> &Repository {
+        &self.repository
+    }
+
+    /// Paints the form as a window, returns false once the user has closed it.
+    pub fn paint(
+        &mut self,
+        ui: &mut Ui,
+        client: &mut github::Client,
+        token: &str,
+        on_dispatched: impl FnOnce() + Send + 'static,
+    ) -> bool {
+        let mut open = true;
+        let mut submit = false;
+        let inputs = self.inputs.lock().unwrap().clone();
+
+        Window::new(format!("Run {}", self.workflow.name)).open(&mut open).show(ui.ctx(), |ui| {
+            ui.label(format!("{} {}", self.repository.full_name, self.workflow.path));
+
+            match &inputs {
+                None => { ui.spinner(); }
+                Some(Err(e)) => { ui.colored_label(Color32::LIGHT_RED, e); }
+                Some(Ok(None)) => { ui.label("This workflow has no workflow_dispatch trigger"); }
+                Some(Ok(Some(inputs))) => {
+                    Grid::new("dispatch_inputs").num_columns(2).show(ui, |ui| {
+                        ui.label("Ref").on_hover_text("Branch or tag to run the workflow on");
+                        ui.text_edit_singleline(&mut self.git_ref);
+                        ui.end_row();
+
+                        inputs.iter().for_each(|input| {
+                            self.paint_input(ui, input);
+                            ui.end_row();
+                        });
+                    });
+
+                    let invalid = inputs.iter().any(|input| !input.accepts(&self.value(input)));
+                    if ui.add_enabled(!invalid && !self.git_ref.is_empty(), Button::new("Run workflow")).clicked() {
+                        submit = true;
+                    }
+                }
+            }
+
+            if let Some(result) = self.result.lock().unwrap().as_ref() {
+                ui.label(result);
+            }
+        });
+
+        if submit {
+            if let Some(Ok(Some(inputs))) = inputs {
+                self.submit(client, token, &inputs, on_dispatched);
+            }
+        }
+
+        open
+    }
+
+    fn paint_input(&mut self, ui: &mut Ui, input: &DispatchInput) {
+        let label = match input.required {
+            true => format!("{} *", input.name),
+            false => input.name.clone(),
+        };
+        ui.label(label).on_hover_text(&input.description);
+
+        let environments = self.environments.lock().unwrap().clone();
+        let value = self.values.entry(input.name.clone()).or_insert_with(|| input.default.clone());
+        match &input.kind {
+            InputKind::String => {
+                ui.text_edit_singleline(value);
+            }
+            InputKind::Number => {
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(value);
+                    if !value.is_empty() && !input.accepts(value) {
+                        ui.colored_label(Color32::LIGHT_RED, "Not a number");
+                    }
+                });
+            }
+            InputKind::Boolean => {
+                let mut checked = value == "true";
+                if ui.checkbox(&mut checked, "").changed() {
+                    *value = checked.to_string();
+                }
+            }
+            InputKind::Choice(options) => combo_box(ui, &input.name, value, options),
+            InputKind::Environment if environments.is_empty() => {
+                ui.text_edit_singleline(value);
+            }
+            InputKind::Environment => combo_box(ui, &input.name, value, &environments),
+        }
+    }
+
+    fn value(&self, input: &DispatchInput) -> String {
+        self.values.get(&input.name).cloned().unwrap_or_else(|| input.default.clone())
+    }
+
+    fn submit(
+        &mut self,
+        client: &mut github::Client,
+        token: &str,
+        inputs: &[DispatchInput],
+        on_dispatched: impl FnOnce() + Send + 'static,
+    ) {
+        let dispatch = Dispatch {
+            git_ref: self.git_ref.clone(),
+            inputs: inputs.iter().map(|input| (input.name.clone(), self.value(input))).collect(),
+        };
+
+        let result = self.result.clone();
+        let git_ref = self.git_ref.clone();
+        let path = format!("/repos/{}/actions/workflows/{}/dispatches", self.repository.full_name, self.workflow.id);
+        *result.lock().unwrap() = Some("Dispatching...".to_string());
+        client.post_path(token, &path, &dispatch, move |response| {
+            match response {
+                Ok(_) => {
+                    *result.lock().unwrap() = Some(format!("Dispatched on {git_ref}"));
+                    on_dispatched();
+                }
+                Err(e) => *result.lock().unwrap() = Some(format!("Dispatch failed: {e}")),
+            }
+        });
+    }
+}
+
+fn combo_box(ui: &mut Ui, id: &str, value: &mut String, options: &[String]) {
+    ComboBox::from_id_source(id).selected_text(value.as_str()).show_ui(ui, |ui| {
+        options.iter().for_each(|option| {
+            ui.selectable_value(value, option.clone(), option);
+        });
+    });
+}
+
+#[cfg(test)]
+mod dispatch {
+    use std::sync::Arc;
+
+    use http::github::Client;
+    use http::transport::FakeTransport;
+    use model::repository::Repository;
+    use model::workflow::Workflow;
+    use model::workflow_dispatch::InputKind;
+
+    use crate::workflow_dispatch::DispatchForm;
+
+    // on:\n  workflow_dispatch:\n    inputs:\n      cluster:\n        type: environment\n        required: true\n
+    const WORKFLOW: &str = "b246CiAgd29ya2Zsb3dfZGlzcGF0Y2g6CiAgICBpbnB1dHM6CiAgICAgIGNsdXN0ZXI6CiAgICAgICAgdHlwZTogZW52aXJvbm1lbnQKICAgICAgICByZXF1aXJlZDogdHJ1ZQo=";
+
+    fn form(transport: Arc<FakeTransport>) -> (Client, DispatchForm) {
+        let repository = r#"{"id":1,"name":"api","full_name":"navikt/api","html_url":"","deployments_url":"","releases_url":"","pulls_url":"","description":null,"archived":false,"default_branch":"main"}"#;
+        let workflow = Workflow { id: 9, name: "Migrate".to_string(), path: ".github/workflows/migrate.yml".to_string(), state: "active".to_string(), ..Workflow::default() };
+        let mut client = Client::default().with_transport(transport);
+        let form = DispatchForm::open(&mut client, "token", serde_json::from_str::<Repository>(repository).unwrap(), workflow);
+        (client, form)
+    }
+
+    fn transport() -> FakeTransport {
+        FakeTransport::default()
+            .respond("https://api.github.com/repos/navikt/api/contents/.github/workflows/migrate.yml", 200, &format!(r#"{{"path":".github/workflows/migrate.yml","encoding":"base64","content":"{WORKFLOW}"}}"#))
+            .respond("https://api.github.com/repos/navikt/api/environments*", 200, r#"{"total_count":1,"environments":[{"url":"","id":1,"name":"prod-gcp","html_url":""}]}"#)
+            .respond("https://api.github.com/repos/navikt/api/actions/workflows/9/dispatches", 204, "")
+    }
+
+    #[test]
+    fn loads_inputs_and_environments() {
+        let (_, form) = form(Arc::new(transport()));
+        let inputs = form.inputs.lock().unwrap().clone().unwrap().unwrap().unwrap();
+        assert_eq!(inputs[0].kind, InputKind::Environment);
+        assert_eq!(form.environments.lock().unwrap().clone(), vec!["prod-gcp"]);
+        assert_eq!(form.git_ref, "main");
+    }
+
+    #[test]
+    fn submit() {
+        let transport = Arc::new(transport());
+        let (mut client, mut form) = form(transport.clone());
+        let inputs = form.inputs.lock().unwrap().clone().unwrap().unwrap().unwrap();
+        form.values.insert("cluster".to_string(), "prod-gcp".to_string());
+
+        let dispatched = Arc::new(std::sync::Mutex::new(false));
+        let _dispatched = dispatched.clone();
+        form.submit(&mut client, "token", &inputs, move || *_dispatched.lock().unwrap() = true);
+
+        assert!(*dispatched.lock().unwrap());
+        assert_eq!(form.result.lock().unwrap().clone().unwrap(), "Dispatched on main");
+        assert_eq!(transport.requests().last().unwrap(), "https://api.github.com/repos/navikt/api/actions/workflows/9/dispatches");
+    }
+}
@@ -0,0 +1,159 @@
+use std::sync::{Arc, Mutex};
+
+use chrono::Utc;
+use egui::{CollapsingHeader, Color32, RichText, Ui, Window};
+
+use http::github;
+use http::pagination::Limit;
+use model::workflow::{Job, WorkflowRun};
+
+use crate::panel_workflows::RunAppearance;
+use crate::timestamp;
+
+type Jobs = Result<Vec<Job>, String>;
+
+/// Jobs and steps of one run, with what failed picked out.
+pub struct JobsView {
+    repo_name: String,
+    run: WorkflowRun,
+    jobs: Arc<Mutex<Option<Jobs>>>,
+}
+
+impl JobsView {
+    pub fn open(client: &mut github::Client, token: &str, repo_name: String, run: WorkflowRun) -> Self {
+        let view = JobsView { repo_name, run, jobs: Arc::default() };
+
+        let jobs = view.jobs.clone();
+        client.get_all::<Job>(token, &view.run.jobs_url, Limit::default(), move |response| {
+            *jobs.lock().unwrap() = Some(response.map_err(|e| e.to_string()));
+        });
+
+        view
+    }
+
+    pub fn run_id(&self) -> i64 {
+        self.run.id
+    }
+
+    /// Paints the jobs as a window, returns false once the user has closed it.
+    pub fn paint(&mut self, ui: &mut Ui) -> bool {
+        let mut open = true;
+        let jobs = self.jobs.lock().unwrap().clone();
+        let title = format!("{} {} #{}", self.repo_name, self.run.name.clone().unwrap_or_default(), self.run.id);
+
+        Window::new(title).id(egui::Id::new("workflow_jobs")).open(&mut open).show(ui.ctx(), |ui| {
+            match &jobs {
+                None => { ui.spinner(); }
+                Some(Err(e)) => { ui.colored_label(Color32::LIGHT_RED, e); }
+                Some(Ok(jobs)) => {
+                    if let Some(summary) = failure_summary(jobs) {
+                        ui.colored_label(Color32::LIGHT_RED, summary);
+                        ui.separator();
+                    }
+                    jobs.iter().for_each(|job| paint_job(ui, job));
+                }
+            }
+        });
+
+        open
+    }
+}
+
+fn paint_job(ui: &mut Ui, job: &Job) {
+    let now = Utc::now();
+    let (color, icon) = job.appearance();
+    let duration = job.duration(now).map(timestamp::duration).unwrap_or_default();
+    let header = RichText::new(format!("{icon} {}  {duration}", job.name)).color(color);
+    let header = match job.failed() {
+        true => header.strong(),
+        false => header,
+    };
+
+    CollapsingHeader::new(header).id_source(job.id).default_open(job.failed()).show(ui, |ui| {
+        ui.horizontal_wrapped(|ui| {
+            if !job.labels.is_empty() {
+                ui.label(format!("runs-on {}", job.labels.join(", ")));
+            }
+            if let Some(runner) = &job.runner_name {
+                ui.label(format!("on {runner}"));
+            }
+            if let Some(url) = &job.html_url {
+                ui.hyperlink_to("Open on GitHub", url);
+            }
+        });
+
+        job.steps.iter().for_each(|step| {
+            ui.horizontal(|ui| {
+                let (color, icon) = step.appearance();
+                let text = RichText::new(format!("{icon} {}. {}", step.number, step.name)).color(color);
+                ui.label(match step.failed() {
+                    true => text.strong(),
+                    false => text,
+                });
+                if let Some(duration) = step.duration(now) {
+                    ui.label(timestamp::duration(duration));
+                }
+            });
+        });
+    });
+}
+
+/// Which jobs failed and in which step, e.g. "Failed in deploy › Deploy to prod-gcp".
+fn failure_summary(jobs: &[Job]) -> Option<String> {
+    let failures = jobs.iter()
+        .filter(|job| job.failed())
+        .map(|job| match job.steps.iter().find(|step| step.failed()) {
+            Some(step) => format!("{} › {}", job.name, step.name),
+            None => job.name.clone(),
+        })
+        .collect::<Vec<_>>();
+
+    match failures.is_empty() {
+        true => None,
+        false => Some(format!("Failed in {}", failures.join(", "))),
+    }
+}
+
+#[cfg(test)]
+mod jobs {
+    use std::sync::Arc;
+
+    use http::github::Client;
+    use http::transport::FakeTransport;
+    use model::workflow::WorkflowRun;
+
+    use crate::workflow_jobs::{failure_summary, JobsView};
+
+    fn step(number: i64, name: &str, conclusion: &str) -> String {
+        format!(r#"{{"name":"{name}","number":{number},"status":"completed","conclusion":"{conclusion}","started_at":"2023-02-01T10:00:00Z","completed_at":"2023-02-01T10:01:00Z"}}"#)
+    }
+
+    fn job(id: i64, name: &str, conclusion: &str, steps: &[String]) -> String {
+        format!(r#"{{"id":{id},"run_id":14,"name":"{name}","status":"completed","conclusion":"{conclusion}","started_at":"2023-02-01T10:00:00Z","completed_at":"2023-02-01T10:05:00Z","html_url":null,"labels":["ubuntu-latest"],"runner_name":null,"steps":[{}]}}"#, steps.join(","))
+    }
+
+    fn run() -> WorkflowRun {
+        WorkflowRun { id: 14, jobs_url: "https://api.github.com/repos/navikt/api/actions/runs/14/jobs".to_string(), ..WorkflowRun::default() }
+    }
+
+    #[test]
+    fn deploy_failed_not_tests() {
+        let body = format!(r#"{{"total_count":2,"jobs":[{},{}]}}"#,
+            job(1, "test", "success", &[step(1, "Set up job", "success"), step(2, "Run tests", "success")]),
+            job(2, "deploy", "failure", &[step(1, "Set up job", "success"), step(2, "Deploy to prod-gcp", "failure"), step(3, "Notify", "skipped")]));
+        let transport = FakeTransport::default().respond("https://api.github.com/repos/navikt/api/actions/runs/14/jobs*", 200, &body);
+        let view = JobsView::open(&mut Client::default().with_transport(Arc::new(transport)), "token", "api".to_string(), run());
+
+        let jobs = view.jobs.lock().unwrap().clone().unwrap().unwrap();
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(failure_summary(&jobs).unwrap(), "Failed in deploy › Deploy to prod-gcp");
+    }
+
+    #[test]
+    fn nothing_failed() {
+        let body = format!(r#"{{"total_count":1,"jobs":[{}]}}"#, job(1, "test", "success", &[]));
+        let transport = FakeTransport::default().respond("*", 200, &body);
+        let view = JobsView::open(&mut Client::default().with_transport(Arc::new(transport)), "token", "api".to_string(), run());
+        assert_eq!(failure_summary(&view.jobs.lock().unwrap().clone().unwrap().unwrap()), None);
+    }
+}